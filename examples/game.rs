@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use tinybit::events::{Event, KeyCode, KeyEvent};
+use tinybit::game::{Control, Game};
+use tinybit::{Camera, Pixel, Viewport, WorldPos, WorldSize};
+
+struct Player {
+    pos: WorldPos,
+    velocity: (f32, f32),
+    camera: Camera<tinybit::camera::NoLimit>,
+}
+
+impl Game for Player {
+    fn update(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        self.pos.x += self.velocity.0 * dt;
+        self.pos.y += self.velocity.1 * dt;
+    }
+
+    fn draw(&mut self, viewport: &mut Viewport, _alpha: f32) {
+        if let Some(pos) = self.camera.to_screen(self.pos) {
+            viewport.draw_pixel(Pixel::white('@', pos));
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> Control {
+        match event {
+            Event::Key(KeyEvent { code: KeyCode::Esc, .. }) => return Control::Quit,
+            Event::Key(KeyEvent { code, .. }) => match code {
+                KeyCode::Left => self.velocity = (-10.0, 0.0),
+                KeyCode::Right => self.velocity = (10.0, 0.0),
+                KeyCode::Up => self.velocity = (0.0, -5.0),
+                KeyCode::Down => self.velocity = (0.0, 5.0),
+                _ => self.velocity = (0.0, 0.0),
+            },
+            Event::Resize(w, h) => self.camera.resize(w, h),
+            _ => {}
+        }
+
+        Control::Continue
+    }
+}
+
+fn main() {
+    let (width, height) = tinybit::term_size().expect("Can't get the term size? Can't play the game!");
+    let pos = WorldPos::new(0.0, 0.0);
+    let camera = Camera::new(pos, WorldSize::new(width as f32, height as f32));
+
+    let player = Player {
+        pos,
+        velocity: (0.0, 0.0),
+        camera,
+    };
+
+    tinybit::run(player).expect("the terminal went away");
+}
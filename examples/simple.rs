@@ -1,9 +1,19 @@
-use tinybit::events::{events, Event, KeyCode, KeyEvent, EventModel};
+use tinybit::events::{events, Event, EventModel};
+use tinybit::input::InputMap;
 use tinybit::{
     term_size, Camera, Renderer, ScreenPos, ScreenSize, StdoutTarget, Viewport,
     WorldPos, WorldSize, Pixel
 };
 
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Action {
+    Quit,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
 fn main() {
     let (width, height) = term_size().expect("Can't get the term size? Can't play the game!");
 
@@ -15,33 +25,48 @@ fn main() {
     let (width, height) = (width as f32, height as f32);
     let camera_size = WorldSize::new(width / 2.0, height / 2.0); let camera_pos =
     WorldPos::new(width, height);
-    let mut camera = Camera::new(camera_pos, camera_size);
+    let camera = Camera::new(camera_pos, camera_size);
 
     // Renderer
     let stdout_renderer = StdoutTarget::new().expect("Failed to enter raw mode");
     let mut renderer = Renderer::new(stdout_renderer);
 
+    // Input
+    let mut input = InputMap::new("gameplay");
+    input.bind(Action::Quit, "esc".parse().unwrap());
+    input.bind(Action::Left, "left".parse().unwrap());
+    input.bind(Action::Right, "right".parse().unwrap());
+    input.bind(Action::Up, "up".parse().unwrap());
+    input.bind(Action::Down, "down".parse().unwrap());
+
     // Player
     let mut player = ('@', camera_pos);
 
     for event in events(EventModel::Fps(20)) {
         match event {
-            Event::Tick => {
-                let pixel = Pixel::new(player.0, camera.to_screen(player.1), None, None);
-                viewport.draw_pixel(pixel);
-                let _ = renderer.render(&mut viewport);
+            Event::Tick(_) => {
+                if let Some(pos) = camera.to_screen(player.1) {
+                    viewport.draw_pixel(Pixel::new(player.0, pos, None, None));
+                }
+                if renderer.render(&mut viewport).is_err() {
+                    // The terminal is gone, nothing left to draw to
+                    break;
+                }
             }
-            Event::Key(KeyEvent { code: KeyCode::Esc, ..  }) => break,
-            Event::Key(KeyEvent { code: kc, .. }) => {
-                match kc {
-                    KeyCode::Left => { player.1.x -= 1.0; }
-                    KeyCode::Right => { player.1.x += 1.0; }
-                    KeyCode::Up => { player.1.y -= 1.0; }
-                    KeyCode::Down => { player.1.y += 1.0; }
-                    _ => {}
+            Event::Key(key) => {
+                match input.action(&key) {
+                    Some(Action::Quit) => break,
+                    Some(Action::Left) => { player.1.x -= 1.0; }
+                    Some(Action::Right) => { player.1.x += 1.0; }
+                    Some(Action::Up) => { player.1.y -= 1.0; }
+                    Some(Action::Down) => { player.1.y += 1.0; }
+                    None => {}
                 }
             }
-            Event::Resize(w, h) => {}
+            Event::Resize(_w, _h) => {}
+            // The screen was cleared, e.g after suspending with Ctrl+Z and resuming
+            Event::Redraw => viewport.invalidate(),
+            Event::Mouse(_) | Event::User(_) => {}
         }
     }
 }
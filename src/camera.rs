@@ -20,17 +20,64 @@ pub struct Camera<T> {
 }
 
 impl<T> Camera<T> {
-    /// Resize the camera
+    /// Resize the camera, keeping it centered on its position
     pub fn resize(&mut self, width: u16, height: u16) {
         self.size = WorldSize::new(width as f32, height as f32);
+        self.update_bounding_box();
     }
 
     /// Convert a point to local space.
-    pub fn to_screen(&self, pos: WorldPos) -> ScreenPos {
-        let min_x = self.bounding_box.min_x();
-        let min_y = self.bounding_box.min_y();
+    /// Returns `None` if the point is outside of the camera's view.
+    pub fn to_screen(&self, pos: WorldPos) -> Option<ScreenPos> {
+        let x = (pos.x - self.bounding_box.min_x()).floor();
+        let y = (pos.y - self.bounding_box.min_y()).floor();
 
-        ScreenPos::new((pos.x - min_x) as u16, (pos.y - min_y) as u16)
+        let in_view = x >= 0.0
+            && y >= 0.0
+            && x < self.bounding_box.width()
+            && y < self.bounding_box.height();
+
+        match in_view {
+            true => Some(ScreenPos::new(x as u16, y as u16)),
+            false => None,
+        }
+    }
+
+    /// Convert a position on screen to a position in the world,
+    /// given the viewport the camera is drawn to.
+    /// This is the inverse of `to_screen`, offset by the viewport's position.
+    ///
+    /// The world position is the top left corner of the cell.
+    /// Returns `None` if the position is outside of the viewport.
+    ///
+    /// ```
+    /// # use tinybit::*;
+    /// let viewport = Viewport::new(ScreenPos::new(2, 2), ScreenSize::new(10, 10));
+    /// let camera = Camera::from_viewport(WorldPos::new(100.0, 100.0), &viewport);
+    /// let clicked = ScreenPos::new(7, 7);
+    /// assert_eq!(camera.to_world(clicked, &viewport), Some(WorldPos::new(100.0, 100.0)));
+    /// ```
+    pub fn to_world(&self, pos: ScreenPos, viewport: &Viewport) -> Option<WorldPos> {
+        let local = viewport.to_local(pos)?;
+        let x = self.bounding_box.min_x() + local.x as f32;
+        let y = self.bounding_box.min_y() + local.y as f32;
+        Some(WorldPos::new(x, y))
+    }
+
+    /// The area of the world the camera can see
+    pub fn bounding_box(&self) -> WorldRect {
+        self.bounding_box
+    }
+
+    /// True if the point is in view of the camera.
+    pub fn is_visible(&self, pos: WorldPos) -> bool {
+        self.to_screen(pos).is_some()
+    }
+
+    /// True if any part of the rect is in view of the camera.
+    /// This is useful for culling entities before drawing them.
+    pub fn is_rect_visible(&self, rect: WorldRect) -> bool {
+        self.bounding_box.intersects(&rect)
     }
 
     /// Move the camera to a new position in global space
@@ -40,8 +87,10 @@ impl<T> Camera<T> {
         }
 
         self.position = new_pos;
+        self.update_bounding_box();
+    }
 
-        // Bounding box
+    fn update_bounding_box(&mut self) {
         self.bounding_box = WorldRect::new(
             WorldPos::new(
                 self.position.x - (self.size.width / 2.0),
@@ -125,6 +174,7 @@ impl Camera<Limit> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::ScreenSize;
 
     fn camera() -> Camera<NoLimit> {
         let pos = WorldPos::new(3.0, 3.0);
@@ -136,7 +186,24 @@ mod test {
     fn world_to_screen_position() {
         let cam = camera();
         let screen_pos = cam.to_screen(cam.bounding_box.origin);
-        assert_eq!(screen_pos, ScreenPos::zero());
+        assert_eq!(screen_pos, Some(ScreenPos::zero()));
+    }
+
+    #[test]
+    fn outside_of_view() {
+        let cam = camera();
+        let origin = cam.bounding_box.origin;
+
+        // Left of and above the camera
+        assert_eq!(cam.to_screen(WorldPos::new(origin.x - 0.5, origin.y)), None);
+        assert_eq!(cam.to_screen(WorldPos::new(origin.x, origin.y - 1.0)), None);
+
+        // Right of and below the camera
+        assert_eq!(cam.to_screen(WorldPos::new(origin.x + 6.0, origin.y)), None);
+        assert_eq!(cam.to_screen(WorldPos::new(origin.x, origin.y + 6.0)), None);
+
+        let last = WorldPos::new(origin.x + 5.5, origin.y + 5.5);
+        assert_eq!(cam.to_screen(last), Some(ScreenPos::new(5, 5)));
     }
 
     #[test]
@@ -183,4 +250,42 @@ mod test {
         cam.track(WorldPos::new(100.0, 97.0));
         assert_eq!(WorldPos::new(100.0, 99.0), cam.position);
     }
+
+    #[test]
+    fn screen_to_world_position() {
+        let viewport = Viewport::new(ScreenPos::new(2, 3), ScreenSize::new(6, 6));
+        let cam = Camera::from_viewport(WorldPos::new(3.0, 3.0), &viewport);
+
+        let world_pos = cam.to_world(ScreenPos::new(4, 4), &viewport).unwrap();
+        assert_eq!(world_pos, WorldPos::new(2.0, 1.0));
+        assert_eq!(cam.to_screen(world_pos), Some(ScreenPos::new(2, 1)));
+
+        // Outside of the viewport
+        assert_eq!(cam.to_world(ScreenPos::new(1, 4), &viewport), None);
+        assert_eq!(cam.to_world(ScreenPos::new(8, 4), &viewport), None);
+    }
+
+    #[test]
+    fn visibility() {
+        let cam = camera();
+        assert!(cam.is_visible(WorldPos::new(0.0, 0.0)));
+        assert!(!cam.is_visible(WorldPos::new(6.0, 0.0)));
+
+        let rect = |x, y| WorldRect::new(WorldPos::new(x, y), WorldSize::new(2.0, 2.0));
+        assert!(cam.is_rect_visible(rect(-1.0, -1.0)));
+        assert!(!cam.is_rect_visible(rect(-2.0, -2.0)));
+        assert!(!cam.is_rect_visible(rect(6.0, 0.0)));
+    }
+
+    #[test]
+    fn resize_camera() {
+        let mut cam = camera();
+        cam.resize(10, 4);
+
+        let expected = WorldRect::new(WorldPos::new(-2.0, 1.0), WorldSize::new(10.0, 4.0));
+        assert_eq!(cam.bounding_box(), expected);
+        assert!(cam.is_visible(WorldPos::new(7.0, 1.0)));
+        assert!(!cam.is_visible(WorldPos::new(0.0, 0.0)));
+        assert_eq!(cam.to_screen(WorldPos::new(-2.0, 1.0)), Some(ScreenPos::zero()));
+    }
 }
@@ -0,0 +1,116 @@
+//! Detect what the terminal supports, going by environment variables.
+//!
+//! `AnsiTarget::with_terminal_setup` uses these to configure the target.
+use std::env;
+
+use crate::ColorMode;
+
+/// True if the terminal is known to support synchronized output
+/// (DEC private mode 2026), where the terminal waits for the end of
+/// a frame before drawing it.
+///
+/// The `TINYBIT_SYNCHRONIZED_OUTPUT` environment variable overrides the check:
+/// `0` turns synchronized output off, anything else turns it on.
+pub fn supports_synchronized_output() -> bool {
+    synchronized_output(|name| env::var(name).ok())
+}
+
+fn synchronized_output(var: impl Fn(&str) -> Option<String>) -> bool {
+    if let Some(value) = var("TINYBIT_SYNCHRONIZED_OUTPUT") {
+        return value != "0";
+    }
+
+    if var("KITTY_WINDOW_ID").is_some() || var("WEZTERM_EXECUTABLE").is_some() {
+        return true;
+    }
+
+    let term = var("TERM").unwrap_or_default();
+    let term_program = var("TERM_PROGRAM").unwrap_or_default();
+    ["kitty", "foot", "wezterm", "alacritty", "contour", "ghostty"]
+        .iter()
+        .any(|name| term.contains(name))
+        || ["WezTerm", "iTerm.app", "ghostty"].contains(&term_program.as_str())
+}
+
+/// The colours the terminal supports, going by the `COLORTERM` and `TERM`
+/// environment variables.
+///
+/// The `TINYBIT_COLOR_MODE` environment variable overrides the check,
+/// and can be one of `truecolor`, `256`, `16` or `monochrome`.
+/// If `NO_COLOR` is set to anything but an empty string,
+/// the colour mode is `ColorMode::Monochrome`.
+pub fn color_mode() -> ColorMode {
+    detect_color_mode(|name| env::var(name).ok())
+}
+
+fn detect_color_mode(var: impl Fn(&str) -> Option<String>) -> ColorMode {
+    match var("TINYBIT_COLOR_MODE").as_deref() {
+        Some("truecolor") => return ColorMode::TrueColor,
+        Some("256") => return ColorMode::Ansi256,
+        Some("16") => return ColorMode::Ansi16,
+        Some("monochrome") => return ColorMode::Monochrome,
+        _ => {}
+    }
+
+    // An empty NO_COLOR doesn't count, see https://no-color.org
+    if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        return ColorMode::Monochrome;
+    }
+
+    if let Some("truecolor") | Some("24bit") = var("COLORTERM").as_deref() {
+        return ColorMode::TrueColor;
+    }
+
+    let term = match var("TERM") {
+        Some(term) => term,
+        // The Windows console doesn't set TERM, and supports true colour
+        None if cfg!(windows) => return ColorMode::TrueColor,
+        None => return ColorMode::Ansi16,
+    };
+
+    if term == "dumb" {
+        ColorMode::Monochrome
+    } else if term.ends_with("direct") || term.contains("truecolor") {
+        ColorMode::TrueColor
+    } else if term.contains("256") || ["xterm", "screen", "tmux", "rxvt"].iter().any(|name| term.starts_with(name)) {
+        ColorMode::Ansi256
+    } else {
+        // e.g the Linux console
+        ColorMode::Ansi16
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vars<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string())
+    }
+
+    #[test]
+    fn detect_synchronized_output() {
+        assert!(synchronized_output(vars(&[("TERM", "xterm-kitty")])));
+        assert!(synchronized_output(vars(&[("TERM", "foot")])));
+        assert!(synchronized_output(vars(&[("TERM", "xterm-256color"), ("TERM_PROGRAM", "WezTerm")])));
+        assert!(!synchronized_output(vars(&[("TERM", "xterm-256color")])));
+        assert!(!synchronized_output(vars(&[])));
+
+        // Override
+        assert!(synchronized_output(vars(&[("TERM", "xterm"), ("TINYBIT_SYNCHRONIZED_OUTPUT", "1")])));
+        assert!(!synchronized_output(vars(&[("TERM", "foot"), ("TINYBIT_SYNCHRONIZED_OUTPUT", "0")])));
+    }
+
+    #[test]
+    fn detect_colors() {
+        let mode = |v| detect_color_mode(vars(v));
+        assert_eq!(mode(&[("COLORTERM", "truecolor"), ("TERM", "xterm-256color")]), ColorMode::TrueColor);
+        assert_eq!(mode(&[("TERM", "xterm-256color")]), ColorMode::Ansi256);
+        assert_eq!(mode(&[("TERM", "xterm")]), ColorMode::Ansi256);
+        assert_eq!(mode(&[("TERM", "linux")]), ColorMode::Ansi16);
+        assert_eq!(mode(&[("TERM", "dumb")]), ColorMode::Monochrome);
+        assert_eq!(mode(&[("TERM", "xterm-256color"), ("NO_COLOR", "1")]), ColorMode::Monochrome);
+        assert_eq!(mode(&[("TERM", "xterm-256color"), ("NO_COLOR", "")]), ColorMode::Ansi256);
+        assert_eq!(mode(&[("TERM", "linux"), ("TINYBIT_COLOR_MODE", "truecolor")]), ColorMode::TrueColor);
+    }
+}
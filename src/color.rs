@@ -0,0 +1,170 @@
+use crate::Color;
+
+/// The colours a terminal can show.
+///
+/// Colours the terminal can't show are replaced by the closest colour it can show.
+///
+/// ```
+/// # use tinybit::*;
+/// let orange = Color::Rgb { r: 255, g: 135, b: 0 };
+/// assert_eq!(ColorMode::TrueColor.convert(orange), Some(orange));
+/// assert_eq!(ColorMode::Ansi256.convert(orange), Some(Color::AnsiValue(208)));
+/// assert_eq!(ColorMode::Ansi16.convert(orange), Some(Color::DarkYellow));
+/// assert_eq!(ColorMode::Monochrome.convert(orange), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// 24 bit colour
+    TrueColor,
+    /// The 256 colour palette
+    Ansi256,
+    /// The 16 named colours
+    Ansi16,
+    /// No colour at all
+    Monochrome,
+}
+
+// The 16 named colours, in palette order, with the default xterm values
+const NAMED: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// The levels of each channel in the 6x6x6 colour cube of the 256 colour palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorMode {
+    /// Convert a colour to the closest colour that can be shown.
+    /// Returns `None` if no colour can be shown.
+    pub fn convert(self, color: Color) -> Option<Color> {
+        match (self, color) {
+            (ColorMode::Monochrome, _) => None,
+            (_, Color::Reset) | (ColorMode::TrueColor, _) => Some(color),
+            (ColorMode::Ansi256, Color::Rgb { r, g, b }) => Some(Color::AnsiValue(nearest_256((r, g, b)))),
+            (ColorMode::Ansi256, _) => Some(color),
+            (ColorMode::Ansi16, Color::AnsiValue(n)) if n < 16 => Some(NAMED[n as usize].0),
+            (ColorMode::Ansi16, Color::AnsiValue(n)) => Some(nearest_named(ansi_rgb(n))),
+            (ColorMode::Ansi16, Color::Rgb { r, g, b }) => Some(nearest_named((r, g, b))),
+            (ColorMode::Ansi16, _) => Some(color),
+        }
+    }
+}
+
+// The SGR parameter for one of the 16 named colours, or the default colour.
+// Used instead of crossterm's commands, which use the 256 colour codes
+// for the named colours.
+pub(crate) fn ansi16_sgr(color: Option<Color>, foreground: bool) -> u8 {
+    let index = color.and_then(|color| NAMED.iter().position(|(named, _)| *named == color));
+    let code = match index {
+        Some(index) if index < 8 => 30 + index as u8,
+        Some(index) => 90 + index as u8 - 8,
+        None => 39,
+    };
+
+    match foreground {
+        true => code,
+        false => code + 10,
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn nearest_named(rgb: (u8, u8, u8)) -> Color {
+    NAMED
+        .iter()
+        .min_by_key(|(_, named)| distance(rgb, *named))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+// The closest colour in the colour cube or the grey ramp.
+// The first 16 colours are left out, as terminals often change them.
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |v: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (**level as i32 - v as i32).abs())
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = 16 + 36 * r + 6 * g + b;
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let grey = 232 + (average.saturating_sub(8) / 10).min(23) as u8;
+
+    match distance(rgb, ansi_rgb(grey)) < distance(rgb, ansi_rgb(cube)) {
+        true => grey,
+        false => cube,
+    }
+}
+
+// The colour of an entry in the 256 colour palette
+fn ansi_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => NAMED[n as usize].1,
+        16..=231 => {
+            let n = n - 16;
+            let level = |i: u8| CUBE_LEVELS[i as usize];
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let v = 8 + 10 * (n - 232);
+            (v, v, v)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn downsample_256() {
+        let mode = ColorMode::Ansi256;
+        assert_eq!(mode.convert(Color::Rgb { r: 0, g: 0, b: 0 }), Some(Color::AnsiValue(16)));
+        assert_eq!(mode.convert(Color::Rgb { r: 255, g: 255, b: 255 }), Some(Color::AnsiValue(231)));
+        assert_eq!(mode.convert(Color::Rgb { r: 128, g: 128, b: 128 }), Some(Color::AnsiValue(244)));
+        assert_eq!(mode.convert(Color::Rgb { r: 0, g: 95, b: 255 }), Some(Color::AnsiValue(27)));
+        assert_eq!(mode.convert(Color::Red), Some(Color::Red));
+        assert_eq!(mode.convert(Color::AnsiValue(100)), Some(Color::AnsiValue(100)));
+    }
+
+    #[test]
+    fn downsample_16() {
+        let mode = ColorMode::Ansi16;
+        assert_eq!(mode.convert(Color::Rgb { r: 250, g: 10, b: 10 }), Some(Color::Red));
+        assert_eq!(mode.convert(Color::Rgb { r: 0, g: 0, b: 200 }), Some(Color::DarkBlue));
+        assert_eq!(mode.convert(Color::AnsiValue(9)), Some(Color::Red));
+        assert_eq!(mode.convert(Color::AnsiValue(232)), Some(Color::Black));
+        assert_eq!(mode.convert(Color::AnsiValue(231)), Some(Color::White));
+        assert_eq!(mode.convert(Color::Reset), Some(Color::Reset));
+    }
+
+    #[test]
+    fn ansi16_codes() {
+        assert_eq!(ansi16_sgr(Some(Color::DarkRed), true), 31);
+        assert_eq!(ansi16_sgr(Some(Color::Red), true), 91);
+        assert_eq!(ansi16_sgr(Some(Color::Red), false), 101);
+        assert_eq!(ansi16_sgr(Some(Color::Reset), true), 39);
+        assert_eq!(ansi16_sgr(None, false), 49);
+    }
+}
@@ -0,0 +1,223 @@
+use std::mem::swap;
+
+use crate::{Pixel, PixelBuffer, ScreenPos, ScreenSize, Viewport};
+
+/// Identifies a viewport in a `Compositor`
+pub type ViewportId = usize;
+
+struct Entry {
+    viewport: Viewport,
+    z_index: u8,
+    visible: bool,
+}
+
+/// Composites multiple viewports into one screen.
+///
+/// Viewports with a higher z index are drawn on top of viewports with a lower z index.
+/// The whole screen is diffed at once, so a viewport that moves or is hidden
+/// doesn't leave anything behind.
+///
+/// ```
+/// # use tinybit::*;
+/// let screen = ScreenSize::new(20, 10);
+/// let mut compositor = Compositor::new(screen);
+/// let map = compositor.push(Viewport::new(ScreenPos::zero(), screen), 0);
+/// let popup = compositor.push(Viewport::new(ScreenPos::new(5, 3), ScreenSize::new(6, 3)), 1);
+///
+/// let mut renderer = Renderer::new(MemoryTarget::new(screen));
+/// compositor.get_mut(popup).unwrap().draw_pixel(Pixel::white('!', ScreenPos::zero()));
+/// renderer.render_compositor(&mut compositor).unwrap();
+///
+/// // Move the popup
+/// compositor.get_mut(popup).unwrap().position = ScreenPos::new(6, 3);
+/// compositor.get_mut(popup).unwrap().draw_pixel(Pixel::white('!', ScreenPos::zero()));
+/// renderer.render_compositor(&mut compositor).unwrap();
+/// assert_eq!(renderer.target().lines()[3], "      !             ");
+/// ```
+pub struct Compositor {
+    size: ScreenSize,
+    viewports: Vec<Option<Entry>>,
+    new_buf: PixelBuffer,
+    old_buf: PixelBuffer,
+    invalidated: bool,
+}
+
+impl Compositor {
+    /// Create a new compositor for a screen of a given size.
+    pub fn new(size: ScreenSize) -> Self {
+        Self {
+            size,
+            viewports: Vec::new(),
+            new_buf: PixelBuffer::new(size),
+            old_buf: PixelBuffer::new(size),
+            invalidated: false,
+        }
+    }
+
+    /// Resize the screen.
+    /// This does not resize any of the viewports.
+    ///
+    /// This invalidates the compositor, so the whole screen is drawn on the next render.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.size = ScreenSize::new(width, height);
+        self.new_buf = PixelBuffer::new(self.size);
+        self.old_buf = PixelBuffer::new(self.size);
+        self.invalidate();
+    }
+
+    /// Draw the whole screen on the next render, not only the cells that changed.
+    ///
+    /// Use this when the screen no longer shows the last frame,
+    /// e.g after clearing the renderer.
+    /// Invalidating the viewports in the compositor has no effect.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    /// Add a viewport with a given z index.
+    pub fn push(&mut self, viewport: Viewport, z_index: u8) -> ViewportId {
+        let id = self.viewports.len();
+        self.viewports.push(Some(Entry {
+            viewport,
+            z_index,
+            visible: true,
+        }));
+        id
+    }
+
+    /// Remove a viewport.
+    pub fn remove(&mut self, id: ViewportId) -> Option<Viewport> {
+        self.viewports
+            .get_mut(id)
+            .and_then(Option::take)
+            .map(|entry| entry.viewport)
+    }
+
+    /// Get a viewport
+    pub fn get(&self, id: ViewportId) -> Option<&Viewport> {
+        self.entry(id).map(|entry| &entry.viewport)
+    }
+
+    /// Get a viewport, mutably
+    pub fn get_mut(&mut self, id: ViewportId) -> Option<&mut Viewport> {
+        self.entry_mut(id).map(|entry| &mut entry.viewport)
+    }
+
+    /// Show or hide a viewport.
+    /// A hidden viewport is still cleared every render.
+    pub fn set_visible(&mut self, id: ViewportId, visible: bool) {
+        if let Some(entry) = self.entry_mut(id) {
+            entry.visible = visible;
+        }
+    }
+
+    /// Change the z index of a viewport.
+    pub fn set_z_index(&mut self, id: ViewportId, z_index: u8) {
+        if let Some(entry) = self.entry_mut(id) {
+            entry.z_index = z_index;
+        }
+    }
+
+    fn entry(&self, id: ViewportId) -> Option<&Entry> {
+        self.viewports.get(id).and_then(Option::as_ref)
+    }
+
+    fn entry_mut(&mut self, id: ViewportId) -> Option<&mut Entry> {
+        self.viewports.get_mut(id).and_then(Option::as_mut)
+    }
+
+    // Composite all visible viewports, from the lowest z index to the highest.
+    fn compose(&mut self) {
+        let Self { viewports, new_buf, .. } = self;
+
+        new_buf.clear();
+
+        let mut entries = viewports.iter_mut().flatten().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.z_index);
+
+        for entry in entries {
+            let position = entry.viewport.position;
+            let frame = entry.viewport.compose();
+            if !entry.visible {
+                continue;
+            }
+
+            for pixel in frame.pixels.iter().flatten() {
+                let x = pixel.pos.x.checked_add(position.x);
+                let y = pixel.pos.y.checked_add(position.y);
+                if let (Some(x), Some(y)) = (x, y) {
+                    new_buf.blend_pixel(Pixel {
+                        pos: ScreenPos::new(x, y),
+                        ..*pixel
+                    });
+                }
+            }
+        }
+    }
+
+    pub(crate) fn pixels(&mut self) -> Vec<Pixel> {
+        self.compose();
+        let pixels = match std::mem::take(&mut self.invalidated) {
+            true => self.new_buf.frame(ScreenPos::zero()),
+            false => self.new_buf.diff(&self.old_buf, ScreenPos::zero()),
+        };
+        swap(&mut self.new_buf, &mut self.old_buf);
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use crate::render::RenderTarget;
+
+    fn compositor() -> (Compositor, ViewportId, ViewportId) {
+        let mut compositor = Compositor::new(ScreenSize::new(6, 2));
+        let map = compositor.push(Viewport::new(ScreenPos::zero(), ScreenSize::new(6, 2)), 0);
+        let popup = compositor.push(Viewport::new(ScreenPos::new(1, 0), ScreenSize::new(2, 1)), 1);
+        (compositor, map, popup)
+    }
+
+    fn draw(compositor: &mut Compositor, map: ViewportId, popup: ViewportId) {
+        let map = compositor.get_mut(map).unwrap();
+        (0..6).for_each(|x| map.draw_pixel(Pixel::white('.', ScreenPos::new(x, 0))));
+        let popup = compositor.get_mut(popup).unwrap();
+        popup.draw_pixel(Pixel::white('#', ScreenPos::new(0, 0)));
+        popup.draw_pixel(Pixel::white('#', ScreenPos::new(1, 0)));
+    }
+
+    #[test]
+    fn overlapping_viewports() {
+        let (mut compositor, map, popup) = compositor();
+        let mut target = MemoryTarget::new(ScreenSize::new(6, 2));
+
+        draw(&mut compositor, map, popup);
+        target.render(compositor.pixels()).unwrap();
+        assert_eq!(target.lines()[0], ".##...");
+
+        // Moving the popup restores the map underneath
+        compositor.get_mut(popup).unwrap().position = ScreenPos::new(3, 0);
+        draw(&mut compositor, map, popup);
+        target.render(compositor.pixels()).unwrap();
+        assert_eq!(target.lines()[0], "...##.");
+
+        // Hiding the popup
+        compositor.set_visible(popup, false);
+        draw(&mut compositor, map, popup);
+        target.render(compositor.pixels()).unwrap();
+        assert_eq!(target.lines()[0], "......");
+    }
+
+    #[test]
+    fn z_index() {
+        let (mut compositor, map, popup) = compositor();
+        let mut target = MemoryTarget::new(ScreenSize::new(6, 2));
+
+        compositor.set_z_index(popup, 0);
+        compositor.set_z_index(map, 1);
+        draw(&mut compositor, map, popup);
+        target.render(compositor.pixels()).unwrap();
+        assert_eq!(target.lines()[0], "......");
+    }
+}
@@ -0,0 +1,73 @@
+//! Errors produced by tinybit.
+use std::fmt;
+use std::io;
+
+use crate::CrosstermError;
+
+/// Result with a tinybit error
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A tinybit error
+#[derive(Debug)]
+pub enum Error {
+    /// Crossterm failed to talk to the terminal
+    Crossterm(CrosstermError),
+
+    /// Reading or writing failed
+    Io(io::Error),
+
+    /// Loading or saving json failed, e.g a file with invalid data
+    Json(serde_json::Error),
+}
+
+impl Error {
+    /// The underlying io error, if any.
+    ///
+    /// This can be used to tell a dead terminal (e.g a broken pipe)
+    /// apart from other errors.
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Crossterm(CrosstermError::IoError(e)) => Some(e),
+            Error::Crossterm(_) | Error::Json(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Crossterm(e) => write!(f, "terminal error: {}", e),
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::Json(e) => write!(f, "json error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Crossterm(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+        }
+    }
+}
+
+impl From<CrosstermError> for Error {
+    fn from(e: CrosstermError) -> Self {
+        Error::Crossterm(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
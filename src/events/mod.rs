@@ -5,45 +5,86 @@
 //! # use tinybit::events::{Event, EventModel};
 //! for event in events::events(EventModel::Fps(20)) {
 //!     match event {
-//!         Event::Tick => {
+//!         Event::Tick(_) => {
 //! #          break
 //!         }
 //!         _ => {}
 //!     }
 //! }
 //! ```
-use std::sync::mpsc::{self, Receiver};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crossterm::event::{read, Event as CrossTermEvent};
+use serde::{Deserialize, Serialize};
 
-pub use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+mod record;
+mod source;
 
-type Rx = Receiver<Event>;
+pub use record::{Recorder, Recording, Replay};
+pub use source::{channel, ChannelSource, EventSender, EventSource, ScriptedSource, TerminalSource, Timer};
+pub use crossterm::event::{
+    KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 
-/// Event. Either a tick event or a key press event
-/// TODO: add resize event
-#[derive(Debug, Clone, Copy)]
-pub enum Event {
+type Rx<T> = Receiver<Event<T>>;
+
+/// Event. Either a tick event, an input event, a resize event,
+/// a redraw event or a custom event of type `T`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Event<T = ()> {
     /// Generated for every frame
-    Tick,
+    Tick(Tick),
 
     /// A key press
     Key(KeyEvent),
 
-    /// Terminal resize event
+    /// A mouse click, release, drag, scroll or move.
+    /// Only generated once mouse capture is enabled on the render target,
+    /// see `AnsiTarget::enable_mouse_capture`.
+    Mouse(MouseEvent),
+
+    /// Terminal resize event.
     Resize(u16, u16),
+
+    /// The screen no longer shows the last frame, e.g after the process
+    /// was suspended and resumed (see the `terminal` module).
+    /// Call `Viewport::invalidate` so everything is drawn on the next render.
+    Redraw,
+
+    /// A custom event, sent through an `EventSender` or a `Timer`
+    User(T),
+}
+
+/// Frame timing, sent with every tick
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Tick {
+    /// Time since the previous tick
+    pub delta: Duration,
+
+    /// The number of ticks before this one
+    pub frame: u64,
+
+    /// The measured number of ticks per second, averaged over the last few ticks
+    pub fps: f64,
 }
 
 /// Events producer
-pub struct Events {
-    rx: Rx,
+pub struct Events<T = ()> {
+    rx: Rx<T>,
     blocking: bool,
 }
 
-impl Iterator for Events {
-    type Item = Event;
+impl<T> Events<T> {
+    /// Record the events, including ticks and their timing,
+    /// so they can be replayed later.
+    pub fn record(self) -> Recorder<Self, T> {
+        Recorder::new(self)
+    }
+}
+
+impl<T> Iterator for Events<T> {
+    type Item = Event<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.blocking {
@@ -56,7 +97,7 @@ impl Iterator for Events {
 
 /// The type of events to listen for.
 pub enum EventModel {
-    /// Generate a tick every N milliseconds
+    /// Generate N ticks per second
     Fps(u64),
     /// Block until an event is raised
     Blocking,
@@ -64,7 +105,7 @@ pub enum EventModel {
     NonBlocking,
 }
 
-/// Produce events.
+/// Produce events from the terminal.
 ///
 /// ```
 /// # use tinybit::*;
@@ -72,44 +113,218 @@ pub enum EventModel {
 /// let model = EventModel::Fps(20);
 /// for event in events::events(model) {
 ///     match event {
-///         Event::Tick => {
+///         Event::Tick(tick) => {
 /// #          break
 ///         }
 ///         _ => {}
 ///     }
 /// }
 /// ```
+///
+/// # Panics
+///
+/// Panics if the event model is `EventModel::Fps(0)`.
 pub fn events(event_model: EventModel) -> Events {
+    events_from(event_model, vec![Box::new(TerminalSource)])
+}
+
+/// Produce events from the given sources.
+///
+/// With `EventModel::Blocking` the events end once all sources
+/// have no more events, which makes it possible to run a game
+/// without a terminal, e.g in a test.
+///
+/// Pass a `ChannelSource` to send `Event::User` events and start timers:
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use tinybit::events::*;
+/// let (sender, source) = channel();
+/// let timer = sender.every(Duration::from_secs(1), "spawn enemy");
+/// for event in events_from(EventModel::Fps(20), vec![Box::new(TerminalSource), Box::new(source)]) {
+///     match event {
+///         Event::User("spawn enemy") => {}
+///         Event::Key(_) => timer.cancel(),
+///         _ => {}
+///     }
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the event model is `EventModel::Fps(0)`.
+pub fn events_from<T: Send + 'static>(
+    event_model: EventModel,
+    sources: Vec<Box<dyn EventSource<T>>>,
+) -> Events<T> {
+    if let EventModel::Fps(fps) = event_model {
+        assert!(fps > 0, "EventModel::Fps needs at least one tick per second");
+    }
+
     let (tx, rx) = mpsc::channel();
 
     // Input events
-    let tx_clone = tx.clone();
-    thread::spawn(move || loop {
-        if let Ok(ev) = read() {
-            match ev {
-                CrossTermEvent::Key(k) => {
-                    let _ = tx_clone.send(Event::Key(k));
-                }
-                CrossTermEvent::Resize(w, h) => {
-                    let _ = tx_clone.send(Event::Resize(w, h));
+    for mut source in sources {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            while let Some(event) = source.next_event() {
+                if tx.send(event).is_err() {
+                    break;
                 }
-                _ => {}
             }
-        }
-    });
+        });
+    }
 
     if let EventModel::Fps(fps) = event_model {
         // Frames
-        thread::spawn(move || loop {
-            let _ = tx.send(Event::Tick);
-            thread::sleep(Duration::from_millis(1000 / fps));
-        });
+        thread::spawn(move || ticks(fps, tx));
     }
 
-    let blocking = match event_model {
-        EventModel::NonBlocking => false,
-        _ => true,
-    };
+    let blocking = !matches!(event_model, EventModel::NonBlocking);
 
     Events { rx, blocking }
 }
+
+// -----------------------------------------------------------------------------
+//     - Ticks -
+// -----------------------------------------------------------------------------
+// Send `fps` ticks per second until the receiver is dropped.
+//
+// Ticks are scheduled against a fixed clock, so the time it takes
+// to send a tick doesn't add up over time.
+// If the ticks fall behind by more than a frame the clock is reset
+// rather than sending a burst of ticks to catch up.
+fn ticks<T>(fps: u64, tx: Sender<Event<T>>) {
+    let interval = Duration::from_secs_f64(1.0 / fps as f64);
+    let mut last = Instant::now();
+    // The first tick is a whole interval after the start, like the rest,
+    // so it doesn't throw off the measured fps
+    let mut next = last + interval;
+    let mut measured_fps = fps as f64;
+
+    for frame in 0.. {
+        let now = Instant::now();
+        if next > now {
+            thread::sleep(next - now);
+        }
+
+        let now = Instant::now();
+        let delta = now - last;
+        last = now;
+
+        if delta > Duration::from_secs(0) {
+            measured_fps = measured_fps * 0.9 + 0.1 / delta.as_secs_f64();
+        }
+
+        let tick = Tick {
+            delta,
+            frame,
+            fps: measured_fps,
+        };
+
+        if tx.send(Event::Tick(tick)).is_err() {
+            break;
+        }
+
+        next += interval;
+        if next + interval < now {
+            next = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tick_timing() {
+        let (tx, rx) = mpsc::channel::<Event>();
+        thread::spawn(move || ticks(100, tx));
+
+        let ticks = rx
+            .iter()
+            .take(20)
+            .map(|event| match event {
+                Event::Tick(tick) => tick,
+                _ => panic!("expected a tick"),
+            })
+            .collect::<Vec<_>>();
+
+        let frames = ticks.iter().map(|tick| tick.frame).collect::<Vec<_>>();
+        assert_eq!(frames, (0..20).collect::<Vec<_>>());
+
+        let elapsed = ticks.iter().map(|tick| tick.delta).sum::<Duration>();
+        assert!(elapsed >= Duration::from_millis(190));
+
+        // Close to the requested rate from the first tick on,
+        // with plenty of room for a slow machine
+        for tick in &ticks {
+            assert!(tick.fps > 50.0 && tick.fps < 300.0, "frame {}: {} fps", tick.frame, tick.fps);
+        }
+    }
+
+    #[test]
+    fn multiple_sources() {
+        let (sender, source) = channel();
+        let script = ScriptedSource::new(vec![
+            (Duration::from_millis(0), Event::Resize(1, 1)),
+            (Duration::from_millis(0), Event::Resize(2, 2)),
+        ]);
+
+        sender.send(Event::Resize(3, 3)).unwrap();
+        drop(sender);
+
+        let events: Events = events_from(EventModel::Blocking, vec![Box::new(script), Box::new(source)]);
+        let mut sizes = events
+            .map(|event| match event {
+                Event::Resize(w, _) => w,
+                _ => panic!("expected a resize event"),
+            })
+            .collect::<Vec<_>>();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn user_events_and_timers() {
+        let (sender, source) = channel();
+        // Wide margins, as the timers run on real time
+        let cancelled = sender.after(Duration::from_millis(20), "cancelled");
+        cancelled.cancel();
+        assert!(cancelled.is_cancelled());
+        sender.after(Duration::from_millis(60), "once");
+        let repeat = sender.every(Duration::from_millis(10), "repeat");
+
+        thread::spawn(move || {
+            let _ = sender.send(Event::User("sent"));
+        });
+
+        let events = events_from(EventModel::Blocking, vec![Box::new(source)]);
+        let mut names = vec![];
+        let mut repeats = 0;
+        for event in events {
+            match event {
+                Event::User("repeat") => repeats += 1,
+                Event::User(name) => names.push(name),
+                _ => panic!("expected a user event"),
+            }
+
+            // Wait for both, in whatever order a busy machine delivers them
+            if names.contains(&"once") && repeats > 0 {
+                repeat.cancel();
+                break;
+            }
+        }
+
+        assert!(names.contains(&"sent"));
+        assert!(!names.contains(&"cancelled"));
+        assert!(repeats >= 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "EventModel::Fps needs at least one tick per second")]
+    fn zero_fps() {
+        events(EventModel::Fps(0));
+    }
+}
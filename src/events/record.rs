@@ -0,0 +1,224 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::{Event, EventSource};
+use crate::error::Result;
+
+// -----------------------------------------------------------------------------
+//     - Recording -
+// -----------------------------------------------------------------------------
+/// A recorded stream of events, including ticks and their timing.
+///
+/// ```no_run
+/// # use tinybit::events::*;
+/// // Record a session
+/// let mut recorder = events(EventModel::Fps(20)).record();
+/// for event in &mut recorder {
+/// #   break
+///     // ...
+/// }
+/// recorder.recording().save("session.json").unwrap();
+///
+/// // Replay it at twice the speed
+/// let recording: Recording = Recording::load("session.json").unwrap();
+/// let replay = recording.replay().speed(2.0);
+/// for event in events_from(EventModel::Blocking, vec![Box::new(replay)]) {
+///     // ...
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording<T = ()> {
+    /// Every event, paired with the time since the previous event
+    pub events: Vec<(Duration, Event<T>)>,
+}
+
+// Derived `Default` would require `T: Default`
+impl<T> Default for Recording<T> {
+    fn default() -> Self {
+        Self { events: Vec::new() }
+    }
+}
+
+impl<T> Recording<T> {
+    /// Save the recording as json.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let json = serde_json::to_string(self)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Load a recording saved with `save`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self>
+    where
+        T: DeserializeOwned,
+    {
+        let json = fs::read_to_string(path)?;
+        let recording = serde_json::from_str(&json)?;
+        Ok(recording)
+    }
+
+    /// Replay the recording, as an event source.
+    pub fn replay(self) -> Replay<T> {
+        Replay {
+            events: self.events.into(),
+            speed: 1.0,
+            last_frame: None,
+            frames: 0,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Recorder -
+// -----------------------------------------------------------------------------
+/// Records all events passing through it.
+/// Created with `Events::record`.
+pub struct Recorder<I, T = ()> {
+    events: I,
+    recording: Recording<T>,
+    last: Instant,
+}
+
+impl<T, I: Iterator<Item = Event<T>>> Recorder<I, T> {
+    pub(crate) fn new(events: I) -> Self {
+        Self {
+            events,
+            recording: Recording::default(),
+            last: Instant::now(),
+        }
+    }
+
+    /// Everything recorded so far
+    pub fn recording(&self) -> &Recording<T> {
+        &self.recording
+    }
+
+    /// Stop recording
+    pub fn into_recording(self) -> Recording<T> {
+        self.recording
+    }
+}
+
+impl<T: Clone, I: Iterator<Item = Event<T>>> Iterator for Recorder<I, T> {
+    type Item = Event<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.events.next()?;
+        let now = Instant::now();
+        self.recording.events.push((now - self.last, event.clone()));
+        self.last = now;
+        Some(event)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Replay -
+// -----------------------------------------------------------------------------
+/// Replays a `Recording`.
+///
+/// The ticks are replayed as they were recorded, so use this with
+/// `EventModel::Blocking` to get the same frames as the recorded session.
+pub struct Replay<T = ()> {
+    events: VecDeque<(Duration, Event<T>)>,
+    speed: f64,
+    last_frame: Option<u64>,
+    frames: u64,
+}
+
+impl<T> Replay<T> {
+    /// Replay speed, where 2.0 is twice as fast as it was recorded.
+    /// Use `f64::INFINITY` to replay without any delays.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the speed is not above zero.
+    pub fn speed(mut self, speed: f64) -> Self {
+        assert!(speed > 0.0, "replay speed has to be above zero");
+        self.speed = speed;
+        self
+    }
+
+    /// Stop the replay after the given number of ticks,
+    /// i.e after `Event::Tick` number `frame` (starting from 0).
+    pub fn stop_at_frame(mut self, frame: u64) -> Self {
+        self.last_frame = Some(frame);
+        self
+    }
+}
+
+impl<T: Send + 'static> EventSource<T> for Replay<T> {
+    fn next_event(&mut self) -> Option<Event<T>> {
+        if let Some(last_frame) = self.last_frame {
+            if self.frames > last_frame {
+                return None;
+            }
+        }
+
+        let (delay, event) = self.events.pop_front()?;
+        thread::sleep(delay.div_f64(self.speed));
+
+        if let Event::Tick(_) = event {
+            self.frames += 1;
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::{events_from, EventModel, Events, KeyCode, ScriptedSource, Tick};
+
+    fn tick(frame: u64) -> Event {
+        Event::Tick(Tick {
+            delta: Duration::from_millis(5),
+            frame,
+            fps: 200.0,
+        })
+    }
+
+    fn session() -> Vec<Event> {
+        vec![tick(0), Event::Key(KeyCode::Left.into()), tick(1), Event::Resize(3, 4), tick(2)]
+    }
+
+    fn record() -> Recording {
+        let script = ScriptedSource::new(session().into_iter().map(|e| (Duration::from_millis(1), e)).collect());
+        let events: Events = events_from(EventModel::Blocking, vec![Box::new(script)]);
+        let mut recorder = events.record();
+        (&mut recorder).for_each(drop);
+        recorder.into_recording()
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let recording = record();
+        assert!(recording.events.iter().all(|(delay, _)| *delay >= Duration::from_millis(1)));
+
+        let path = std::env::temp_dir().join("tinybit_recording.json");
+        recording.save(&path).unwrap();
+        let loaded = Recording::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(recording, loaded);
+
+        let replay = loaded.replay().speed(f64::INFINITY);
+        let events = events_from(EventModel::Blocking, vec![Box::new(replay)]);
+        assert_eq!(events.collect::<Vec<_>>(), session());
+    }
+
+    #[test]
+    fn stop_at_frame() {
+        let replay = record().replay().speed(f64::INFINITY).stop_at_frame(1);
+        let events = events_from(EventModel::Blocking, vec![Box::new(replay)]);
+        assert_eq!(events.collect::<Vec<_>>(), session()[..3].to_vec());
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{poll, read, Event as CrossTermEvent};
+
+use super::Event;
+use crate::terminal;
+
+// -----------------------------------------------------------------------------
+//     - Event source -
+// -----------------------------------------------------------------------------
+/// Something that produces events, like the terminal.
+///
+/// Every source passed to `events_from` is polled on its own thread.
+///
+/// `T` is the type of `Event::User` events.
+pub trait EventSource<T = ()>: Send + 'static {
+    /// Block until the next event is available.
+    /// Returns `None` once the source has no more events.
+    fn next_event(&mut self) -> Option<Event<T>>;
+}
+
+// -----------------------------------------------------------------------------
+//     - Terminal -
+// -----------------------------------------------------------------------------
+/// Key, mouse and resize events from the terminal.
+///
+/// This also sends an `Event::Redraw` when the screen has to be
+/// drawn again after the process was suspended.
+pub struct TerminalSource;
+
+impl<T> EventSource<T> for TerminalSource {
+    fn next_event(&mut self) -> Option<Event<T>> {
+        // Errors are retried rather than ending the input, e.g reading
+        // fails while the process is in the background after being resumed.
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            match poll_terminal() {
+                Ok(Some(event)) => return Some(event),
+                Ok(None) => backoff = MIN_BACKOFF,
+                Err(_) => {
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+}
+
+const MIN_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+// Poll rather than block on `read`, to notice when the screen has to be redrawn
+fn poll_terminal<T>() -> crate::error::Result<Option<Event<T>>> {
+    if !poll(Duration::from_millis(100))? {
+        if terminal::take_redraw() {
+            return Ok(Some(Event::Redraw));
+        }
+        return Ok(None);
+    }
+
+    let event = match read()? {
+        CrossTermEvent::Key(k) => Event::Key(k),
+        CrossTermEvent::Mouse(m) => Event::Mouse(m),
+        CrossTermEvent::Resize(w, h) => Event::Resize(w, h),
+    };
+    Ok(Some(event))
+}
+
+// -----------------------------------------------------------------------------
+//     - Scripted -
+// -----------------------------------------------------------------------------
+/// A fixed list of events, each produced after a delay.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use tinybit::events::*;
+/// let script = ScriptedSource::new(vec![
+///     (Duration::from_millis(0), Event::Key(KeyCode::Up.into())),
+///     (Duration::from_millis(5), Event::Key(KeyCode::Esc.into())),
+/// ]);
+/// let events: Events = events_from(EventModel::Blocking, vec![Box::new(script)]);
+/// assert_eq!(events.count(), 2);
+/// ```
+pub struct ScriptedSource<T = ()> {
+    events: VecDeque<(Duration, Event<T>)>,
+}
+
+impl<T> ScriptedSource<T> {
+    /// Create a new scripted source.
+    /// Each event is produced after waiting for the duration paired with it.
+    pub fn new(events: Vec<(Duration, Event<T>)>) -> Self {
+        Self {
+            events: events.into(),
+        }
+    }
+}
+
+impl<T: Send + 'static> EventSource<T> for ScriptedSource<T> {
+    fn next_event(&mut self) -> Option<Event<T>> {
+        let (delay, event) = self.events.pop_front()?;
+        thread::sleep(delay);
+        Some(event)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Channel -
+// -----------------------------------------------------------------------------
+/// Events sent through an `EventSender`.
+/// Created with `channel`.
+pub struct ChannelSource<T = ()> {
+    rx: Receiver<Event<T>>,
+}
+
+impl<T: Send + 'static> EventSource<T> for ChannelSource<T> {
+    fn next_event(&mut self) -> Option<Event<T>> {
+        self.rx.recv().ok()
+    }
+}
+
+/// Send events to a `ChannelSource`.
+///
+/// This can be cloned and moved to other threads, to wake up
+/// the main loop with `Event::User` events once background work is done.
+///
+/// ```no_run
+/// # use std::thread;
+/// # use tinybit::events::*;
+/// enum Loaded {
+///     Level(Vec<String>),
+/// }
+///
+/// let (sender, source) = channel();
+/// thread::spawn(move || {
+///     let level = vec!["#..#".to_string()];
+///     let _ = sender.send(Event::User(Loaded::Level(level)));
+/// });
+///
+/// for event in events_from(EventModel::Blocking, vec![Box::new(TerminalSource), Box::new(source)]) {
+///     if let Event::User(Loaded::Level(level)) = event {
+///         // ...
+/// #       break
+///     }
+/// }
+/// ```
+pub struct EventSender<T = ()> {
+    tx: Sender<Event<T>>,
+}
+
+// Derived `Clone` would require `T: Clone`
+impl<T> Clone for EventSender<T> {
+    fn clone(&self) -> Self {
+        Self { tx: self.tx.clone() }
+    }
+}
+
+impl<T: Send + 'static> EventSender<T> {
+    /// Send an event.
+    /// This fails if the source is dropped.
+    pub fn send(&self, event: Event<T>) -> Result<(), SendError<Event<T>>> {
+        self.tx.send(event)
+    }
+
+    /// Send `Event::User(event)` once, after the delay.
+    pub fn after(&self, delay: Duration, event: T) -> Timer {
+        let timer = Timer::new();
+        let cancelled = timer.cancelled.clone();
+        let tx = self.tx.clone();
+
+        thread::spawn(move || {
+            thread::sleep(delay);
+            if !cancelled.load(Ordering::SeqCst) {
+                let _ = tx.send(Event::User(event));
+            }
+        });
+
+        timer
+    }
+
+    /// Send `Event::User(event)` every `interval`, until the timer is
+    /// cancelled or the source is dropped.
+    ///
+    /// Note that the timer holds on to a sender, so a blocking event
+    /// stream won't end while a repeating timer is running.
+    pub fn every(&self, interval: Duration, event: T) -> Timer
+    where
+        T: Clone,
+    {
+        let timer = Timer::new();
+        let cancelled = timer.cancelled.clone();
+        let tx = self.tx.clone();
+
+        thread::spawn(move || {
+            let mut next = Instant::now() + interval;
+            loop {
+                let now = Instant::now();
+                if next > now {
+                    thread::sleep(next - now);
+                }
+
+                if cancelled.load(Ordering::SeqCst) || tx.send(Event::User(event.clone())).is_err() {
+                    break;
+                }
+
+                next += interval;
+            }
+        });
+
+        timer
+    }
+}
+
+/// Create a source that produces the events sent through the sender.
+/// The source has no more events once all senders are dropped.
+pub fn channel<T>() -> (EventSender<T>, ChannelSource<T>) {
+    let (tx, rx) = mpsc::channel();
+    (EventSender { tx }, ChannelSource { rx })
+}
+
+// -----------------------------------------------------------------------------
+//     - Timers -
+// -----------------------------------------------------------------------------
+/// A timer started with `EventSender::after` or `EventSender::every`.
+///
+/// Dropping the timer does not stop it, use `cancel` for that.
+#[derive(Debug, Clone)]
+pub struct Timer {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Timer {
+    fn new() -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Stop the timer.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// True if the timer was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,244 @@
+//! A game loop with a fixed time step.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use tinybit::events::{Event, KeyCode, KeyEvent};
+//! use tinybit::game::{Control, Game};
+//! use tinybit::{Pixel, ScreenPos, Viewport};
+//!
+//! struct Ball {
+//!     x: f32,
+//!     prev_x: f32,
+//! }
+//!
+//! impl Game for Ball {
+//!     fn update(&mut self, dt: Duration) {
+//!         self.prev_x = self.x;
+//!         self.x += 10.0 * dt.as_secs_f32();
+//!     }
+//!
+//!     fn draw(&mut self, viewport: &mut Viewport, alpha: f32) {
+//!         let x = self.prev_x + (self.x - self.prev_x) * alpha;
+//!         viewport.draw_pixel(Pixel::white('o', ScreenPos::new(x as u16, 0)));
+//!     }
+//!
+//!     fn handle_event(&mut self, event: Event) -> Control {
+//!         match event {
+//!             Event::Key(KeyEvent { code: KeyCode::Esc, .. }) => Control::Quit,
+//!             _ => Control::Continue,
+//!         }
+//!     }
+//! }
+//!
+//! tinybit::run(Ball { x: 0.0, prev_x: 0.0 }).expect("the terminal went away");
+//! ```
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::events::{events, Event, EventModel, Events};
+use crate::render::RenderTarget;
+use crate::{term_size, Renderer, ScreenPos, ScreenSize, StdoutTarget, Viewport};
+
+/// Whether the game loop should keep running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Keep running
+    Continue,
+    /// Stop the game loop
+    Quit,
+}
+
+/// A game driven by `run`.
+///
+/// `T` is the type of `Event::User` events, see `run_with`.
+pub trait Game<T = ()> {
+    /// Advance the game by a fixed time step.
+    fn update(&mut self, dt: Duration);
+
+    /// Draw the game.
+    ///
+    /// `alpha` is how far between the previous and the next update
+    /// the frame is, from 0.0 to 1.0. It can be used to interpolate
+    /// positions for smoother movement.
+    fn draw(&mut self, viewport: &mut Viewport, alpha: f32);
+
+    /// Handle an input or resize event.
+    /// Ticks are handled by the game loop.
+    fn handle_event(&mut self, event: Event<T>) -> Control;
+
+    /// The fixed time step passed to `update`. Has to be greater than zero.
+    fn update_step(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / 30.0)
+    }
+
+    /// The number of frames drawn per second.
+    fn fps(&self) -> u64 {
+        30
+    }
+}
+
+// The most time the game loop will catch up on in one frame,
+// as a number of update steps.
+// Without this a slow frame leads to more updates, leading to even slower frames.
+const MAX_STEPS_PER_FRAME: u32 = 8;
+
+/// Run a game on the terminal until it quits.
+///
+/// This sets up the terminal, and draws the game on a viewport
+/// covering the whole terminal.
+pub fn run(mut game: impl Game) -> Result<()> {
+    let (width, height) = term_size()?;
+    let viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(width, height));
+    let mut renderer = Renderer::new(StdoutTarget::new()?);
+    let events = events(EventModel::Fps(game.fps()));
+    run_with(&mut game, &mut renderer, viewport, events)
+}
+
+/// Run a game with a given renderer, viewport and events until it quits,
+/// or until there are no more events.
+///
+/// The viewport is resized on every `Event::Resize`, and invalidated on
+/// every `Event::Redraw`, so the whole screen is drawn again on the next tick.
+///
+/// To wake the game up from other threads or with timers, create the events
+/// with `events_from`, passing a `ChannelSource` along with the `TerminalSource`.
+///
+/// # Panics
+///
+/// Panics if the game's `update_step` is zero.
+pub fn run_with<E, G: Game<E>, T: RenderTarget>(
+    game: &mut G,
+    renderer: &mut Renderer<T>,
+    mut viewport: Viewport,
+    events: Events<E>,
+) -> Result<()> {
+    let step = game.update_step();
+    assert!(step > Duration::from_secs(0), "Game::update_step has to be greater than zero");
+    let mut lag = Duration::from_secs(0);
+
+    for event in events {
+        match event {
+            Event::Tick(tick) => {
+                lag = (lag + tick.delta).min(step * MAX_STEPS_PER_FRAME);
+                while lag >= step {
+                    game.update(step);
+                    lag -= step;
+                }
+
+                let alpha = lag.as_secs_f32() / step.as_secs_f32();
+                game.draw(&mut viewport, alpha);
+                renderer.render(&mut viewport)?;
+                continue;
+            }
+            Event::Resize(width, height) => {
+                viewport.resize(width, height);
+                renderer.clear()?;
+            }
+            Event::Redraw => viewport.invalidate(),
+            _ => {}
+        }
+
+        if let Control::Quit = game.handle_event(event) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::{events_from, KeyCode, ScriptedSource, Tick};
+    use crate::*;
+
+    #[derive(Default)]
+    struct Counter {
+        updates: u32,
+        alpha: f32,
+        size: ScreenSize,
+    }
+
+    impl Game for Counter {
+        fn update(&mut self, _dt: Duration) {
+            self.updates += 1;
+        }
+
+        fn draw(&mut self, viewport: &mut Viewport, alpha: f32) {
+            self.alpha = alpha;
+            self.size = viewport.size;
+            let glyph = std::char::from_digit(self.updates, 10).unwrap();
+            viewport.draw_pixel(Pixel::white(glyph, ScreenPos::zero()));
+        }
+
+        fn handle_event(&mut self, event: Event) -> Control {
+            match event {
+                Event::Key(key) if key.code == KeyCode::Esc => Control::Quit,
+                _ => Control::Continue,
+            }
+        }
+
+        fn update_step(&self) -> Duration {
+            Duration::from_millis(10)
+        }
+    }
+
+    fn tick(ms: u64) -> (Duration, Event) {
+        let tick = Tick {
+            delta: Duration::from_millis(ms),
+            frame: 0,
+            fps: 0.0,
+        };
+        (Duration::from_millis(0), Event::Tick(tick))
+    }
+
+    #[test]
+    fn fixed_time_step() {
+        let script = ScriptedSource::new(vec![
+            tick(25),
+            tick(5),
+            (Duration::from_millis(0), Event::Resize(4, 2)),
+            tick(0),
+            (Duration::from_millis(0), Event::Key(KeyCode::Esc.into())),
+            tick(10),
+        ]);
+        let events = events_from(EventModel::Blocking, vec![Box::new(script)]);
+
+        let mut game = Counter::default();
+        let mut renderer = Renderer::new(MemoryTarget::new(ScreenSize::new(4, 2)));
+        let viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(2, 2));
+        run_with(&mut game, &mut renderer, viewport, events).unwrap();
+
+        // 25ms is two updates with 5ms left over, the next 5ms makes the third update.
+        // The last tick is never handled as the game quit before it.
+        assert_eq!(game.updates, 3);
+        assert_eq!(game.alpha, 0.0);
+        assert_eq!(game.size, ScreenSize::new(4, 2));
+        assert_eq!(renderer.target().lines()[0], "3   ");
+    }
+
+    #[test]
+    #[should_panic(expected = "Game::update_step has to be greater than zero")]
+    fn zero_update_step() {
+        struct Stalled;
+
+        impl Game for Stalled {
+            fn update(&mut self, _dt: Duration) {}
+
+            fn draw(&mut self, _viewport: &mut Viewport, _alpha: f32) {}
+
+            fn handle_event(&mut self, _event: Event) -> Control {
+                Control::Continue
+            }
+
+            fn update_step(&self) -> Duration {
+                Duration::from_secs(0)
+            }
+        }
+
+        let events = events_from(EventModel::Blocking, vec![Box::new(ScriptedSource::new(vec![tick(10)]))]);
+        let mut renderer = Renderer::new(MemoryTarget::new(ScreenSize::new(2, 2)));
+        let viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(2, 2));
+        let _ = run_with(&mut Stalled, &mut renderer, viewport, events);
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const CAPACITY: usize = 15;
+
+/// A single grapheme cluster, drawn in one or two cells on screen.
+///
+/// Most glyphs are one cell wide. Wide glyphs (CJK, most emoji) are
+/// two cells wide, and the second cell holds a `Glyph::CONTINUATION`.
+///
+/// Grapheme clusters longer than 15 bytes are truncated.
+///
+/// ```
+/// # use tinybit::Glyph;
+/// assert_eq!(Glyph::from('a').width(), 1);
+/// assert_eq!(Glyph::from('中').width(), 2);
+/// assert_eq!(Glyph::from("e\u{301}").width(), 1);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Glyph {
+    bytes: [u8; CAPACITY],
+    len: u8,
+}
+
+impl Glyph {
+    /// The second cell of a wide glyph.
+    /// Nothing is drawn for a continuation as the wide glyph to the left covers it.
+    pub const CONTINUATION: Glyph = Glyph {
+        bytes: [0; CAPACITY],
+        len: 0,
+    };
+
+    /// Create a glyph from a grapheme cluster.
+    pub fn new(s: &str) -> Self {
+        let mut len = s.len().min(CAPACITY);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let mut bytes = [0; CAPACITY];
+        bytes[..len].copy_from_slice(&s.as_bytes()[..len]);
+        Self { bytes, len: len as u8 }
+    }
+
+    /// The glyph as a string slice
+    pub fn as_str(&self) -> &str {
+        // The bytes are always copied from a `str`, on a char boundary
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+
+    /// The number of cells the glyph occupies on screen: 0, 1 or 2.
+    pub fn width(&self) -> u16 {
+        self.as_str().width().min(2) as u16
+    }
+
+    /// True if this is the second cell of a wide glyph
+    pub fn is_continuation(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl From<char> for Glyph {
+    fn from(c: char) -> Self {
+        let mut buf = [0; 4];
+        Self::new(c.encode_utf8(&mut buf))
+    }
+}
+
+impl From<&str> for Glyph {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl PartialEq<char> for Glyph {
+    fn eq(&self, other: &char) -> bool {
+        let mut buf = [0; 4];
+        self.as_str() == other.encode_utf8(&mut buf)
+    }
+}
+
+impl fmt::Display for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl Serialize for Glyph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Glyph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(Self::new(&s))
+    }
+}
+
+/// Split a line of text into glyphs, paired with the column
+/// each glyph starts at.
+/// Zero width grapheme clusters (e.g a lone combining mark) are skipped.
+pub(crate) fn columns(line: &str) -> impl Iterator<Item = (u16, Glyph)> + '_ {
+    line.graphemes(true)
+        .map(Glyph::new)
+        .scan(0, |column, glyph| {
+            let start = *column;
+            *column += glyph.width();
+            Some((start, glyph))
+        })
+        .filter(|(_, glyph)| glyph.width() > 0)
+}
@@ -0,0 +1,665 @@
+//! Map key events to game actions.
+//!
+//! Bindings are written as strings, like `"left"`, `"a"`, `"ctrl+s"` or `"shift+f5"`,
+//! so an `InputMap` can be loaded from a config file where players rebind keys:
+//!
+//! ```json
+//! {
+//!   "gameplay": { "Left": ["left", "a"], "Save": ["ctrl+s"] },
+//!   "menu": { "Back": ["esc", "backspace"] }
+//! }
+//! ```
+//!
+//! ```
+//! # use tinybit::events::{Event, KeyCode, KeyEvent, KeyModifiers};
+//! # use tinybit::input::InputMap;
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+//! enum Action {
+//!     Left,
+//!     Save,
+//!     Back,
+//! }
+//!
+//! let mut input = InputMap::new("gameplay");
+//! input.bind(Action::Left, "left".parse().unwrap());
+//! input.bind(Action::Left, "a".parse().unwrap());
+//! input.bind(Action::Save, "ctrl+s".parse().unwrap());
+//! input.context_mut("menu").bind(Action::Back, "esc".parse().unwrap());
+//!
+//! let save = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+//! assert_eq!(input.action(&save), Some(&Action::Save));
+//!
+//! input.set_context("menu");
+//! assert_eq!(input.action(&save), None);
+//! assert_eq!(input.action(&KeyCode::Esc.into()), Some(&Action::Back));
+//! ```
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::mem;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::Result;
+use crate::events::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+// -----------------------------------------------------------------------------
+//     - Binding -
+// -----------------------------------------------------------------------------
+/// A key, pressed together with zero or more modifiers.
+///
+/// Parsed from, and displayed as, modifiers followed by the key,
+/// joined with `+`: `"ctrl+alt+delete"`.
+///
+/// Letters are case insensitive, but an upper case letter implies shift:
+/// `"A"` is the same binding as `"shift+a"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Binding {
+    /// Create a new binding
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let (code, modifiers) = normalize(code, modifiers);
+        Self { code, modifiers }
+    }
+
+    /// The key
+    pub fn code(&self) -> KeyCode {
+        self.code
+    }
+
+    /// The modifiers that have to be held down with the key
+    pub fn modifiers(&self) -> KeyModifiers {
+        self.modifiers
+    }
+
+    /// True if the key event triggers this binding.
+    /// The modifiers have to match exactly.
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        *self == Binding::new(event.code, event.modifiers)
+    }
+}
+
+impl From<KeyCode> for Binding {
+    fn from(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+}
+
+impl From<KeyEvent> for Binding {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+// Terminals report upper case letters with shift held,
+// and other characters that need shift without it.
+// Back tab is always shift+tab, some terminals report the shift and some don't.
+fn normalize(code: KeyCode, mut modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::Char(c) if c.is_alphabetic() => {
+            if c.is_uppercase() {
+                modifiers.insert(KeyModifiers::SHIFT);
+            }
+            let c = c.to_lowercase().next().unwrap_or(c);
+            (KeyCode::Char(c), modifiers)
+        }
+        KeyCode::Char(c) => (KeyCode::Char(c), modifiers - KeyModifiers::SHIFT),
+        KeyCode::BackTab => (KeyCode::BackTab, modifiers - KeyModifiers::SHIFT),
+        code => (code, modifiers),
+    }
+}
+
+const MODIFIERS: [(&str, KeyModifiers); 3] = [
+    ("ctrl", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("shift", KeyModifiers::SHIFT),
+];
+
+const KEYS: [(&str, KeyCode); 16] = [
+    ("backspace", KeyCode::Backspace),
+    ("enter", KeyCode::Enter),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("esc", KeyCode::Esc),
+    ("space", KeyCode::Char(' ')),
+];
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, modifier) in &MODIFIERS {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match KEYS.iter().find(|(_, code)| *code == self.code) {
+            Some((name, _)) => write!(f, "{}", name),
+            None => match self.code {
+                KeyCode::F(n) => write!(f, "f{}", n),
+                KeyCode::Char(c) => write!(f, "{}", c),
+                _ => write!(f, "null"),
+            },
+        }
+    }
+}
+
+/// A binding that could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBindingError(String);
+
+impl fmt::Display for ParseBindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid key binding: \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ParseBindingError {}
+
+impl FromStr for Binding {
+    type Err = ParseBindingError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || ParseBindingError(s.to_string());
+
+        // The plus key is the one key that can't come after a `+`
+        let (modifier_names, key) = match s.strip_suffix("++") {
+            Some(modifier_names) => (modifier_names, "+"),
+            None if s == "+" => ("", "+"),
+            None => match s.rfind('+') {
+                Some(i) => (&s[..i], &s[i + 1..]),
+                None => ("", s),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        if !modifier_names.is_empty() {
+            for name in modifier_names.split('+') {
+                let name = name.to_lowercase();
+                match MODIFIERS.iter().find(|(n, _)| *n == name) {
+                    Some((_, modifier)) => modifiers.insert(*modifier),
+                    None => return Err(error()),
+                }
+            }
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => {
+                let key = key.to_lowercase();
+                match KEYS.iter().find(|(name, _)| *name == key) {
+                    Some((_, code)) => *code,
+                    None if key == "null" => KeyCode::Null,
+                    None => match key.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                        Some(n) => KeyCode::F(n),
+                        None => return Err(error()),
+                    },
+                }
+            }
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Bindings -
+// -----------------------------------------------------------------------------
+/// Actions and the bindings that trigger them, for one context.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Bindings<A: Ord> {
+    actions: BTreeMap<A, Vec<Binding>>,
+}
+
+impl<A: Ord> Bindings<A> {
+    /// Create an empty set of bindings
+    pub fn new() -> Self {
+        Self {
+            actions: BTreeMap::new(),
+        }
+    }
+
+    /// Bind a key to an action.
+    /// An action can have any number of bindings.
+    pub fn bind(&mut self, action: A, binding: Binding) {
+        let bindings = self.actions.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Remove a binding from every action it's bound to
+    pub fn unbind(&mut self, binding: Binding) {
+        self.actions.values_mut().for_each(|bindings| bindings.retain(|b| *b != binding));
+        self.actions.retain(|_, bindings| !bindings.is_empty());
+    }
+
+    /// Remove all bindings for an action
+    pub fn clear_action(&mut self, action: &A) {
+        self.actions.remove(action);
+    }
+
+    /// The bindings for an action
+    pub fn bindings(&self, action: &A) -> &[Binding] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// The action triggered by the key event.
+    /// If a key is bound to more than one action, the first action is returned.
+    pub fn action(&self, event: &KeyEvent) -> Option<&A> {
+        self.actions
+            .iter()
+            .find(|(_, bindings)| bindings.iter().any(|b| b.matches(event)))
+            .map(|(action, _)| action)
+    }
+}
+
+impl<A: Ord> Default for Bindings<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Input map -
+// -----------------------------------------------------------------------------
+/// Bindings for a number of named contexts, like "menu" and "gameplay",
+/// where only the bindings of the active context are used.
+///
+/// Serialised as a map of context names to bindings.
+/// The active context is not serialised: after deserialising,
+/// the first context (in alphabetical order) is active.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InputMap<A: Ord> {
+    #[serde(flatten)]
+    contexts: BTreeMap<String, Bindings<A>>,
+    #[serde(skip)]
+    active: String,
+}
+
+impl<A: Ord> InputMap<A> {
+    /// Create an input map with a single, active, context.
+    pub fn new(context: impl Into<String>) -> Self {
+        let context = context.into();
+        let mut contexts = BTreeMap::new();
+        contexts.insert(context.clone(), Bindings::new());
+        Self {
+            contexts,
+            active: context,
+        }
+    }
+
+    /// Load an input map from a json file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self>
+    where
+        A: DeserializeOwned,
+    {
+        let json = fs::read_to_string(path)?;
+        let map = serde_json::from_str(&json)?;
+        Ok(map)
+    }
+
+    /// Save the input map as json
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()>
+    where
+        A: Serialize,
+    {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Make a context active, creating it if it doesn't exist.
+    pub fn set_context(&mut self, context: &str) {
+        self.context_mut(context);
+        self.active = context.to_string();
+    }
+
+    /// The name of the active context
+    pub fn active_context(&self) -> &str {
+        &self.active
+    }
+
+    /// The bindings of a context
+    pub fn context(&self, context: &str) -> Option<&Bindings<A>> {
+        self.contexts.get(context)
+    }
+
+    /// The bindings of a context, creating the context if it doesn't exist.
+    pub fn context_mut(&mut self, context: &str) -> &mut Bindings<A> {
+        self.contexts.entry(context.to_string()).or_default()
+    }
+
+    /// Bind a key to an action in the active context
+    pub fn bind(&mut self, action: A, binding: Binding) {
+        let active = self.active.clone();
+        self.context_mut(&active).bind(action, binding);
+    }
+
+    /// The action triggered by the key event in the active context
+    pub fn action(&self, event: &KeyEvent) -> Option<&A> {
+        self.contexts.get(&self.active)?.action(event)
+    }
+}
+
+impl<'de, A: Ord + Deserialize<'de>> Deserialize<'de> for InputMap<A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let contexts = BTreeMap::<String, Bindings<A>>::deserialize(deserializer)?;
+        let active = contexts.keys().next().cloned().unwrap_or_default();
+        Ok(Self { contexts, active })
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Input state -
+// -----------------------------------------------------------------------------
+/// Which keys are held down.
+///
+/// Terminals only send key presses, and repeated presses while a key is held,
+/// so a key counts as held until no press or repeat is seen for the timeout.
+/// The timeout should be longer than the delay before the operating system
+/// starts repeating a key, or keys are released before the first repeat.
+///
+/// Real key release events (e.g through the kitty keyboard protocol)
+/// are not supported by the version of crossterm used here,
+/// so the timeout is the only way a key is released.
+///
+/// Pass every event to `handle_event`. The state for a frame is updated
+/// on `Event::Tick`, so `just_pressed` and `just_released` are true for
+/// one frame only:
+///
+/// ```
+/// # use std::time::Duration;
+/// # use tinybit::events::{Event, KeyCode, Tick};
+/// # use tinybit::input::InputState;
+/// let mut input = InputState::new().timeout(Duration::from_millis(100));
+/// let left: Event = Event::Key(KeyCode::Left.into());
+/// let tick: Event = Event::Tick(Tick { delta: Duration::from_millis(60), frame: 0, fps: 0.0 });
+///
+/// input.handle_event(&left);
+/// input.handle_event(&tick);
+/// assert!(input.just_pressed(KeyCode::Left));
+///
+/// input.handle_event(&tick);
+/// assert!(input.is_down(KeyCode::Left));
+/// assert!(!input.just_pressed(KeyCode::Left));
+///
+/// input.handle_event(&tick);
+/// assert!(input.just_released(KeyCode::Left));
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputState {
+    timeout: Duration,
+    // Time since the last press or repeat of every held key
+    held: HashMap<KeyCode, Duration>,
+    // Keys seen since the last tick
+    seen: HashSet<KeyCode>,
+    pressed: HashSet<KeyCode>,
+    released: HashSet<KeyCode>,
+}
+
+impl InputState {
+    /// Create a new input state with a timeout of 500 ms.
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_millis(500),
+            held: HashMap::new(),
+            seen: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+        }
+    }
+
+    /// The time after the last press or repeat before a key is released.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Update the state with an event.
+    /// Key events are recorded, and a tick moves on to the next frame.
+    pub fn handle_event<T>(&mut self, event: &Event<T>) {
+        match event {
+            Event::Key(key) => {
+                self.seen.insert(key_code(key.code));
+            }
+            Event::Tick(tick) => self.tick(tick.delta),
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        self.pressed.clear();
+        self.released.clear();
+
+        let timeout = self.timeout;
+        let released = &mut self.released;
+        self.held.retain(|code, since| {
+            *since += delta;
+            let held = *since < timeout;
+            if !held {
+                released.insert(*code);
+            }
+            held
+        });
+
+        for code in mem::take(&mut self.seen) {
+            if self.held.insert(code, Duration::from_secs(0)).is_none() && !self.released.remove(&code) {
+                self.pressed.insert(code);
+            }
+        }
+    }
+
+    /// True if the key is held down
+    pub fn is_down(&self, code: KeyCode) -> bool {
+        self.held.contains_key(&key_code(code))
+    }
+
+    /// True if the key was pressed this frame
+    pub fn just_pressed(&self, code: KeyCode) -> bool {
+        self.pressed.contains(&key_code(code))
+    }
+
+    /// True if the key was released this frame
+    pub fn just_released(&self, code: KeyCode) -> bool {
+        self.released.contains(&key_code(code))
+    }
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Holding shift down while a letter key is held changes the letter,
+// but not the key.
+fn key_code(code: KeyCode) -> KeyCode {
+    normalize(code, KeyModifiers::NONE).0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    enum Action {
+        Left,
+        Right,
+        Save,
+        Back,
+    }
+
+    fn tick(ms: u64) -> Event {
+        Event::Tick(crate::events::Tick {
+            delta: Duration::from_millis(ms),
+            frame: 0,
+            fps: 0.0,
+        })
+    }
+
+    fn key(key: impl Into<KeyEvent>) -> Event {
+        Event::Key(key.into())
+    }
+
+    fn binding(s: &str) -> Binding {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_bindings() {
+        assert_eq!(binding("left"), KeyCode::Left.into());
+        assert_eq!(binding("ctrl+alt+Delete"), Binding::new(KeyCode::Delete, KeyModifiers::CONTROL | KeyModifiers::ALT));
+        assert_eq!(binding("f12"), KeyCode::F(12).into());
+        assert_eq!(binding("space"), KeyCode::Char(' ').into());
+        assert_eq!(binding("ctrl++"), Binding::new(KeyCode::Char('+'), KeyModifiers::CONTROL));
+        assert_eq!(binding("+"), KeyCode::Char('+').into());
+        assert_eq!(binding("A"), binding("shift+a"));
+        assert!("hyper+a".parse::<Binding>().is_err());
+        assert!("leftt".parse::<Binding>().is_err());
+
+        for s in &["ctrl+alt+shift+f5", "a", "space", "ctrl++", "esc"] {
+            assert_eq!(binding(s).to_string(), *s);
+        }
+    }
+
+    #[test]
+    fn match_key_events() {
+        let mut bindings = Bindings::new();
+        bindings.bind(Action::Left, binding("left"));
+        bindings.bind(Action::Left, binding("h"));
+        bindings.bind(Action::Save, binding("ctrl+s"));
+
+        assert_eq!(bindings.action(&KeyCode::Left.into()), Some(&Action::Left));
+        assert_eq!(bindings.action(&KeyCode::Char('h').into()), Some(&Action::Left));
+        assert_eq!(bindings.action(&KeyEvent::new(KeyCode::Char('H'), KeyModifiers::SHIFT)), None);
+        assert_eq!(bindings.action(&KeyCode::Char('s').into()), None);
+        assert_eq!(bindings.action(&KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)), Some(&Action::Save));
+
+        bindings.unbind(binding("left"));
+        assert_eq!(bindings.bindings(&Action::Left), &[binding("h")]);
+        bindings.clear_action(&Action::Left);
+        assert!(bindings.bindings(&Action::Left).is_empty());
+    }
+
+    #[test]
+    fn match_back_tab() {
+        let mut bindings = Bindings::new();
+        bindings.bind(Action::Back, binding("backtab"));
+
+        assert_eq!(bindings.action(&KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)), Some(&Action::Back));
+        assert_eq!(bindings.action(&KeyCode::BackTab.into()), Some(&Action::Back));
+        assert_eq!(binding("shift+backtab"), binding("backtab"));
+    }
+
+    #[test]
+    fn contexts_and_serde() {
+        let mut input = InputMap::new("gameplay");
+        input.bind(Action::Left, binding("left"));
+        input.bind(Action::Right, binding("right"));
+        input.context_mut("menu").bind(Action::Back, binding("esc"));
+
+        let json = serde_json::to_string(&input).unwrap();
+        assert_eq!(
+            json,
+            r#"{"gameplay":{"Left":["left"],"Right":["right"]},"menu":{"Back":["esc"]}}"#
+        );
+
+        let mut input: InputMap<Action> = serde_json::from_str(&json).unwrap();
+        assert_eq!(input.active_context(), "gameplay");
+        assert_eq!(input.action(&KeyCode::Right.into()), Some(&Action::Right));
+        assert_eq!(input.action(&KeyCode::Esc.into()), None);
+
+        input.set_context("menu");
+        assert_eq!(input.action(&KeyCode::Right.into()), None);
+        assert_eq!(input.action(&KeyCode::Esc.into()), Some(&Action::Back));
+    }
+
+    #[test]
+    fn held_keys() {
+        let mut input = InputState::new().timeout(Duration::from_millis(100));
+        let left = key(KeyCode::Left);
+
+        input.handle_event(&left);
+        assert!(!input.is_down(KeyCode::Left));
+        input.handle_event(&tick(20));
+        assert!(input.is_down(KeyCode::Left));
+        assert!(input.just_pressed(KeyCode::Left));
+
+        // Repeats keep the key down
+        for _ in 0..5 {
+            input.handle_event(&tick(60));
+            input.handle_event(&left);
+            assert!(input.is_down(KeyCode::Left));
+            assert!(!input.just_pressed(KeyCode::Left));
+        }
+
+        // The last repeat is seen on the first tick
+        input.handle_event(&tick(60));
+        input.handle_event(&tick(60));
+        assert!(input.is_down(KeyCode::Left));
+        input.handle_event(&tick(60));
+        assert!(!input.is_down(KeyCode::Left));
+        assert!(input.just_released(KeyCode::Left));
+        input.handle_event(&tick(60));
+        assert!(!input.just_released(KeyCode::Left));
+    }
+
+    #[test]
+    fn held_key_case() {
+        let mut input = InputState::new();
+        input.handle_event(&key(KeyCode::Char('a')));
+        input.handle_event(&tick(10));
+        input.handle_event(&key(KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT)));
+        input.handle_event(&tick(10));
+        assert!(input.is_down(KeyCode::Char('A')));
+        assert!(!input.just_pressed(KeyCode::Char('a')));
+    }
+
+    #[test]
+    fn press_after_release() {
+        // A key pressed again the frame it would have been released stays down
+        let mut input = InputState::new().timeout(Duration::from_millis(50));
+        let up = key(KeyCode::Up);
+        input.handle_event(&up);
+        input.handle_event(&tick(10));
+        input.handle_event(&up);
+        input.handle_event(&tick(100));
+        assert!(input.is_down(KeyCode::Up));
+        assert!(!input.just_pressed(KeyCode::Up));
+        assert!(!input.just_released(KeyCode::Up));
+    }
+}
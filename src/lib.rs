@@ -29,9 +29,10 @@
 //! 
 //!     for event in events(EventModel::Fps(20)) {
 //!         match event {
-//!             Event::Tick => {
-//!                 let pixel = Pixel::new(player.0, camera.to_screen(player.1), None, None);
-//!                 viewport.draw_pixel(pixel);
+//!             Event::Tick(_) => {
+//!                 if let Some(pos) = camera.to_screen(player.1) {
+//!                     viewport.draw_pixel(Pixel::new(player.0, pos, None, None));
+//!                 }
 //!                 let _ = renderer.render(&mut viewport);
 //! #               break
 //!             }
@@ -46,52 +47,159 @@
 //!                 }
 //!             }
 //!             Event::Resize(w, h) => {}
+//!             Event::Redraw => viewport.invalidate(),
+//!             Event::Mouse(_) | Event::User(_) => {}
 //!         }
 //!     }
 //! }
 //! ```
 
+use std::ops::BitOr;
+
+use crossterm::style::Attribute;
 use serde::{Serialize, Deserialize};
 
+mod color;
+mod compositor;
+mod glyph;
 mod pixelbuffer;
 mod viewport;
 
+pub mod error;
 pub mod render;
 pub mod camera;
+pub mod capabilities;
 pub mod events;
+pub mod game;
+pub mod input;
+pub mod terminal;
+pub mod theme;
 pub mod widgets;
 
 /// A character at a position, with a colour
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Pixel {
-    pub glyph: char,
+    pub glyph: Glyph,
     pub pos: ScreenPos,
     pub fg_color: Option<Color>,
     pub bg_color: Option<Color>,
+    #[serde(default)]
+    pub attributes: Attributes,
 }
 
 impl Pixel {
-    pub fn new(glyph: char, pos: ScreenPos, fg_color: Option<Color>, bg_color: Option<Color>) -> Self {
+    pub fn new(glyph: impl Into<Glyph>, pos: ScreenPos, fg_color: Option<Color>, bg_color: Option<Color>) -> Self {
         Self {
-            glyph,
+            glyph: glyph.into(),
             pos,
             fg_color,
             bg_color,
+            attributes: Attributes::empty(),
         }
     } 
 
-    pub fn white(c: char, pos: ScreenPos) -> Self {
+    pub fn white(c: impl Into<Glyph>, pos: ScreenPos) -> Self {
         Self::new(c, pos, None, None)
     }
+
+    /// Set the text attributes of the pixel.
+    ///
+    /// ```
+    /// # use tinybit::*;
+    /// let pixel = Pixel::white('A', ScreenPos::zero())
+    ///     .with_attributes(Attributes::BOLD | Attributes::UNDERLINE);
+    /// assert!(pixel.attributes.contains(Attributes::BOLD));
+    /// ```
+    pub fn with_attributes(mut self, attributes: Attributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Attributes -
+// -----------------------------------------------------------------------------
+/// A set of text attributes, like bold or underline.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Attributes(u8);
+
+impl Attributes {
+    /// Bold text
+    pub const BOLD: Attributes = Attributes(1);
+    /// Dim text
+    pub const DIM: Attributes = Attributes(1 << 1);
+    /// Italic text
+    pub const ITALIC: Attributes = Attributes(1 << 2);
+    /// Underlined text
+    pub const UNDERLINE: Attributes = Attributes(1 << 3);
+    /// Blinking text
+    pub const BLINK: Attributes = Attributes(1 << 4);
+    /// Swap the foreground and background colour
+    pub const REVERSE: Attributes = Attributes(1 << 5);
+
+    const ALL: [(Attributes, Attribute); 6] = [
+        (Attributes::BOLD, Attribute::Bold),
+        (Attributes::DIM, Attribute::Dim),
+        (Attributes::ITALIC, Attribute::Italic),
+        (Attributes::UNDERLINE, Attribute::Underlined),
+        (Attributes::BLINK, Attribute::SlowBlink),
+        (Attributes::REVERSE, Attribute::Reverse),
+    ];
+
+    /// No attributes
+    pub const fn empty() -> Self {
+        Attributes(0)
+    }
+
+    /// True if no attributes are set
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// True if all the attributes in `other` are set
+    pub fn contains(self, other: Attributes) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Set the attributes in `other`
+    pub fn insert(&mut self, other: Attributes) {
+        self.0 |= other.0;
+    }
+
+    /// Unset the attributes in `other`
+    pub fn remove(&mut self, other: Attributes) {
+        self.0 &= !other.0;
+    }
+
+    /// The terminal attributes to set
+    pub(crate) fn terminal_attributes(self) -> impl Iterator<Item = Attribute> {
+        Self::ALL
+            .iter()
+            .filter(move |(attr, _)| self.contains(*attr))
+            .map(|(_, term_attr)| *term_attr)
+    }
+}
+
+impl BitOr for Attributes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Attributes(self.0 | rhs.0)
+    }
 }
 
 // -----------------------------------------------------------------------------
 //     - Reexports -
 // -----------------------------------------------------------------------------
 pub use camera::Camera;
+pub use color::ColorMode;
+pub use compositor::{Compositor, ViewportId};
+pub use error::Error;
+pub use game::{run, Game};
+pub use glyph::Glyph;
 pub use pixelbuffer::PixelBuffer;
 pub use crossterm::terminal::size as term_size;
-pub use render::{Renderer, StdoutTarget};
+pub use render::{AnsiTarget, MemoryTarget, Renderer, StdoutTarget};
 pub use viewport::Viewport;
 pub use crossterm::style::{Colored, Color};
 pub use crossterm::ErrorKind as CrosstermError;
@@ -1,4 +1,4 @@
-use crate::{Pixel, ScreenPos, ScreenSize};
+use crate::{Glyph, Pixel, ScreenPos, ScreenSize};
 
 /// Character buffer holds all the "pixels" to be drawn on the screen
 #[derive(Debug)]
@@ -12,7 +12,7 @@ impl PixelBuffer {
     /// Create a new character buffer.
     /// Should be at least the same size as the viewport it's relative to
     pub fn new(size: ScreenSize) -> Self {
-        let cap = (size.width * size.height) as usize;
+        let cap = size.width as usize * size.height as usize;
         let pixels = vec![None; cap];
         Self { pixels, size }
     }
@@ -26,23 +26,229 @@ impl PixelBuffer {
 
     /// Get the pixel at the given screen position
     pub fn get_pixel(&self, pos: ScreenPos) -> Option<Pixel> {
-        let index = (self.size.width * pos.y + pos.x) as usize;
+        let index = self.index(pos)?;
         match self.pixels.get(index) {
             Some(c) => *c,
             None => None,
         }
     }
 
-    pub(crate) fn set_pixel(&mut self, pixel: Pixel) {
-        let index = (self.size.width * pixel.pos.y + pixel.pos.x) as usize;
+    /// Clear all pixels
+    pub(crate) fn clear(&mut self) {
+        self.pixels.iter_mut().for_each(|opt| {
+            opt.take();
+        });
+    }
+
+    /// Resize the buffer, keeping the pixels that still fit.
+    pub(crate) fn resize(&mut self, size: ScreenSize) {
+        let old = std::mem::replace(self, PixelBuffer::new(size));
+        old.pixels.into_iter().flatten().for_each(|pixel| self.set_pixel(pixel));
+    }
+
+    /// Every cell, offset by `offset`.
+    /// Cells without a pixel are a blank space.
+    pub(crate) fn frame(&self, offset: ScreenPos) -> Vec<Pixel> {
+        self.pixels
+            .iter()
+            .enumerate()
+            .map(|(index, pixel)| {
+                let pos = self.index_to_coords(index);
+                let pos = ScreenPos::new(pos.x.saturating_add(offset.x), pos.y.saturating_add(offset.y));
+                match pixel {
+                    Some(pixel) => Pixel { pos, ..*pixel },
+                    None => Pixel::white(' ', pos),
+                }
+            })
+            .collect()
+    }
+
+    /// The pixels that changed since `old`, offset by `offset`.
+    /// Pixels that are no longer drawn are replaced by a blank space.
+    pub(crate) fn diff(&self, old: &PixelBuffer, offset: ScreenPos) -> Vec<Pixel> {
+        let mut pixels = Vec::<Pixel>::new();
+        let offset = |index| {
+            let pos = self.index_to_coords(index);
+            ScreenPos::new(pos.x.saturating_add(offset.x), pos.y.saturating_add(offset.y))
+        };
+
+        for (new, old) in self.pixels.iter().enumerate().zip(&old.pixels) {
+            match (new, old) {
+                // Unchanged since the last frame
+                ((_, Some(pixel)), Some(old_pixel)) if pixel == old_pixel => {}
+                ((index, Some(pixel)), _) => {
+                    let mut pixel = *pixel;
+                    pixel.pos = offset(index);
+                    pixels.push(pixel);
+                }
+                ((index, None), Some(_)) => {
+                    pixels.push(Pixel::white(' ', offset(index)));
+                }
+                ((_, None), None) => {}
+            }
+        }
+
+        pixels
+    }
+
+    fn index(&self, pos: ScreenPos) -> Option<usize> {
+        if pos.x >= self.size.width || pos.y >= self.size.height {
+            return None;
+        }
+        Some(self.size.width as usize * pos.y as usize + pos.x as usize)
+    }
+
+    /// Set a pixel, keeping wide glyphs intact:
+    /// * A wide glyph also fills the cell to its right with a continuation.
+    /// * A wide glyph that doesn't fit on the row is replaced by a space.
+    /// * Overwriting either half of a wide glyph replaces the other half with a space.
+    ///
+    /// Continuations and zero width glyphs are ignored, as they can't occupy a cell on their own.
+    pub(crate) fn set_pixel(&mut self, mut pixel: Pixel) {
+        let index = match self.index(pixel.pos) {
+            Some(index) => index,
+            None => return,
+        };
+
+        let width = pixel.glyph.width();
+        if width == 0 {
+            return;
+        }
+
         if let Some(Some(existing_pixel)) = self.pixels.get(index) {
             if existing_pixel.glyph == pixel.glyph
                 && existing_pixel.fg_color == pixel.fg_color
                 && existing_pixel.bg_color == pixel.bg_color
+                && existing_pixel.attributes == pixel.attributes
             {
                 return;
             }
         }
+
+        self.break_wide_glyph(pixel.pos);
+
+        if width == 2 {
+            let next = ScreenPos::new(pixel.pos.x.saturating_add(1), pixel.pos.y);
+            match self.index(next) {
+                Some(next_index) => {
+                    self.break_wide_glyph(next);
+                    self.pixels[next_index] = Some(Pixel {
+                        glyph: Glyph::CONTINUATION,
+                        pos: next,
+                        ..pixel
+                    });
+                }
+                None => pixel.glyph = Glyph::from(' '),
+            }
+        }
+
         self.pixels[index] = Some(pixel);
     }
+
+    /// Set a pixel on top of the existing pixel.
+    /// If the pixel has no background colour, the background
+    /// colour of the existing pixel is kept.
+    pub(crate) fn blend_pixel(&mut self, mut pixel: Pixel) {
+        // Continuations are added by the wide glyph to their left
+        if pixel.glyph.is_continuation() {
+            return;
+        }
+
+        if pixel.bg_color.is_none() {
+            pixel.bg_color = self.get_pixel(pixel.pos).and_then(|p| p.bg_color);
+        }
+        self.set_pixel(pixel);
+    }
+
+    // If the cell at `pos` is part of a wide glyph,
+    // replace the other half of the glyph with a space.
+    fn break_wide_glyph(&mut self, pos: ScreenPos) {
+        let existing = match self.get_pixel(pos) {
+            Some(pixel) => pixel,
+            None => return,
+        };
+
+        let other = if existing.glyph.is_continuation() {
+            pos.x.checked_sub(1).map(|x| ScreenPos::new(x, pos.y))
+        } else if existing.glyph.width() == 2 {
+            Some(ScreenPos::new(pos.x.saturating_add(1), pos.y))
+        } else {
+            None
+        };
+
+        if let Some(index) = other.and_then(|other| self.index(other)) {
+            if let Some(pixel) = &mut self.pixels[index] {
+                pixel.glyph = Glyph::from(' ');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn buffer() -> PixelBuffer {
+        PixelBuffer::new(ScreenSize::new(4, 1))
+    }
+
+    fn glyphs(buffer: &PixelBuffer) -> Vec<Option<Glyph>> {
+        buffer.pixels.iter().map(|p| p.map(|p| p.glyph)).collect()
+    }
+
+    #[test]
+    fn wide_glyph_fills_two_cells() {
+        let mut buf = buffer();
+        buf.set_pixel(Pixel::white('中', ScreenPos::new(1, 0)));
+        assert_eq!(
+            glyphs(&buf),
+            vec![None, Some('中'.into()), Some(Glyph::CONTINUATION), None]
+        );
+    }
+
+    #[test]
+    fn wide_glyph_on_last_column() {
+        let mut buf = buffer();
+        buf.set_pixel(Pixel::white('中', ScreenPos::new(3, 0)));
+        assert_eq!(glyphs(&buf), vec![None, None, None, Some(' '.into())]);
+    }
+
+    #[test]
+    fn overwrite_half_of_wide_glyph() {
+        let mut buf = buffer();
+        buf.set_pixel(Pixel::white('中', ScreenPos::new(0, 0)));
+        buf.set_pixel(Pixel::white('a', ScreenPos::new(1, 0)));
+        assert_eq!(
+            glyphs(&buf),
+            vec![Some(' '.into()), Some('a'.into()), None, None]
+        );
+
+        buf.set_pixel(Pixel::white('中', ScreenPos::new(2, 0)));
+        buf.set_pixel(Pixel::white('b', ScreenPos::new(2, 0)));
+        assert_eq!(
+            glyphs(&buf),
+            vec![Some(' '.into()), Some('a'.into()), Some('b'.into()), Some(' '.into())]
+        );
+    }
+
+    #[test]
+    fn zero_width_glyph_is_ignored() {
+        let mut buf = buffer();
+        buf.set_pixel(Pixel::white('\u{301}', ScreenPos::new(0, 0)));
+        assert_eq!(glyphs(&buf), vec![None, None, None, None]);
+    }
+
+    #[test]
+    fn offset_near_the_edge() {
+        let mut buf = buffer();
+        buf.set_pixel(Pixel::white('a', ScreenPos::new(3, 0)));
+        let offset = ScreenPos::new(u16::MAX - 1, u16::MAX);
+
+        let xs = buf.frame(offset).iter().map(|p| p.pos.x).collect::<Vec<_>>();
+        assert_eq!(xs, vec![u16::MAX - 1, u16::MAX, u16::MAX, u16::MAX]);
+
+        let diff = buf.diff(&buffer(), offset);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].pos, ScreenPos::new(u16::MAX, u16::MAX));
+    }
 }
@@ -1,57 +1,60 @@
+use std::fmt;
+use std::fs;
+use std::env;
 use std::io::{self, Stdout, Write};
+use std::path::Path;
 
 use crossterm::cursor::{self, MoveTo};
-use crossterm::style::{SetBackgroundColor, SetForegroundColor};
+use crossterm::style::{Attribute, SetAttribute, SetBackgroundColor, SetForegroundColor};
 
-#[cfg(target_os = "windows")]
-use crossterm::event::EnableMouseCapture;
-
-#[cfg(not(target_os = "windows"))]
-use crossterm::event::DisableMouseCapture;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 
 use crossterm::style::Print;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen,
 };
 use crossterm::QueueableCommand;
-use crossterm::{execute, ExecutableCommand, Result};
+use crossterm::{execute, ExecutableCommand};
 
-use crate::{Color, Pixel, Viewport};
+use crate::capabilities;
+use crate::color::ansi16_sgr;
+use crate::error::{Error, Result};
+use crate::terminal;
+use crate::{Attributes, Color, ColorMode, Compositor, Pixel, PixelBuffer, ScreenPos, ScreenSize, Viewport};
 
 // -----------------------------------------------------------------------------
-//     - Setup terminal for stdout target -
+//     - Setup terminal -
 // -----------------------------------------------------------------------------
-fn setup_terminal_for_stdout_target() -> Result<Stdout> {
+pub(crate) fn setup_terminal<W: Write>(writer: &mut W) -> Result<()> {
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    stdout.execute(EnterAlternateScreen)?;
+    writer.execute(EnterAlternateScreen)?;
 
-    // we enable mouse capture because:
-    // 1) DisableMouseCapture doesn't work on windows.
-    // 2) it allows to add mouse support later if needed
+    // we enable mouse capture on windows because
+    // DisableMouseCapture doesn't work on windows.
+    // Everywhere else mouse capture is opt-in, see `enable_mouse_capture`.
     //
     // ! if you want to disable mouse capture, be sure to enable it first,
     // ! or it will crash on windows.
     #[cfg(target_os = "windows")]
-    execute!(stdout, EnableMouseCapture,)?;
+    execute!(writer, EnableMouseCapture,)?;
 
     #[cfg(not(target_os = "windows"))]
-    execute!(stdout, DisableMouseCapture,)?;
+    execute!(writer, DisableMouseCapture,)?;
 
-    stdout.execute(cursor::Hide)?;
-    stdout.execute(Clear(ClearType::All))?;
-    Ok(stdout)
+    writer.execute(cursor::Hide)?;
+    writer.execute(Clear(ClearType::All))?;
+    Ok(())
 }
 
 // -----------------------------------------------------------------------------
-//     - Reset terminal from stdout target -
+//     - Reset terminal -
 // -----------------------------------------------------------------------------
-fn reset_terminal_from_stdout_target(stdout: &mut Stdout) -> Result<()> {
+pub(crate) fn reset_terminal<W: Write>(writer: &mut W) -> Result<()> {
     // Do we need to show the cursor too, or does that get handled
     // automatically by crossterm?
 
-    stdout.execute(cursor::Show)?;
-    stdout.execute(LeaveAlternateScreen)?;
+    writer.execute(cursor::Show)?;
+    writer.execute(LeaveAlternateScreen)?;
     disable_raw_mode()?;
 
     Ok(())
@@ -72,13 +75,32 @@ impl<T: RenderTarget> Renderer<T> {
     }
 
     /// Draw characters to screen
-    pub fn render(&mut self, viewport: &mut Viewport) {
-        self.target.render(viewport.pixels());
+    pub fn render(&mut self, viewport: &mut Viewport) -> Result<()> {
+        self.target.render(viewport.pixels())
+    }
+
+    /// Draw all viewports in a compositor to screen
+    pub fn render_compositor(&mut self, compositor: &mut Compositor) -> Result<()> {
+        self.target.render(compositor.pixels())
+    }
+
+    /// Clear the screen.
+    ///
+    /// Viewports only produce the pixels that changed since the last render,
+    /// so anything that doesn't change won't be drawn again after this,
+    /// unless the viewport is invalidated with `Viewport::invalidate`.
+    pub fn clear(&mut self) -> Result<()> {
+        self.target.clear()
+    }
+
+    /// The render target
+    pub fn target(&self) -> &T {
+        &self.target
     }
 
-    /// Clear the screen
-    pub fn clear(&mut self) {
-        self.target.clear();
+    /// The render target, mutably
+    pub fn target_mut(&mut self) -> &mut T {
+        &mut self.target
     }
 }
 
@@ -87,82 +109,312 @@ impl<T: RenderTarget> Renderer<T> {
 // -----------------------------------------------------------------------------
 /// Something that a render can render to.
 pub trait RenderTarget {
-    fn render(&mut self, pixels: Vec<Pixel>);
-    fn clear(&mut self);
+    fn render(&mut self, pixels: Vec<Pixel>) -> Result<()>;
+    fn clear(&mut self) -> Result<()>;
 }
 
 // -----------------------------------------------------------------------------
-//     - Stdout render target -
+//     - Ansi render target -
 // -----------------------------------------------------------------------------
-/// Render to stdout
-pub struct StdoutTarget {
-    stdout: Stdout,
+/// Render to anything that implements `Write`, using ANSI escape codes.
+///
+/// This can be used to render to a file, a socket or a pipe:
+///
+/// ```
+/// # use tinybit::*;
+/// # use tinybit::render::{AnsiTarget, RenderTarget};
+/// let mut target = AnsiTarget::from_writer(Vec::new());
+/// target.render(vec![Pixel::white('@', ScreenPos::new(1, 2))]).unwrap();
+/// assert!(!target.writer().is_empty());
+/// ```
+pub struct AnsiTarget<W: Write> {
+    writer: W,
+    terminal_setup: bool,
+    // Set if the terminal is restored on panic and signals, see the `terminal` module
+    terminal_hooks: bool,
+    mouse_capture: bool,
+    synchronized_output: bool,
+    color_mode: ColorMode,
     last_color_fg: Option<Color>,
     last_color_bg: Option<Color>,
+    last_attributes: Attributes,
+    // The last style is unknown once the process was resumed
+    resumes: usize,
 }
 
-impl StdoutTarget {
-    /// Create a new stdout target.
-    /// This sets up the terminal so tinybit can draw on it. That includes:
+impl<W: Write> AnsiTarget<W> {
+    /// Create a new target writing to `writer`.
+    /// This does not touch the terminal settings.
+    pub fn from_writer(writer: W) -> Self {
+        Self {
+            writer,
+            terminal_setup: false,
+            terminal_hooks: false,
+            mouse_capture: false,
+            synchronized_output: false,
+            color_mode: ColorMode::TrueColor,
+            last_color_fg: None,
+            last_color_bg: None,
+            last_attributes: Attributes::empty(),
+            resumes: terminal::resumes(),
+        }
+    }
+
+    /// Create a new target writing to `writer`,
+    /// and set up the terminal so tinybit can draw on it. That includes:
     /// * Enabling raw mode
     /// * Entering an alternate screen
     /// * Hiding the cursor
     /// * Clearing the screen
     ///
     /// Once this is dropped it will reset all these settings.
-    pub fn new() -> Result<Self> {
-        let stdout = setup_terminal_for_stdout_target()?;
-        Ok(Self {
-            stdout,
-            last_color_fg: None,
-            last_color_bg: None,
-        })
+    /// Unlike `StdoutTarget::new`, the settings are not reset on panic or on signals,
+    /// as that writes to stdout rather than to `writer`.
+    ///
+    /// Synchronized output is enabled if the terminal supports it,
+    /// see `capabilities::supports_synchronized_output`,
+    /// and the colour mode is set to the colours the terminal supports,
+    /// see `capabilities::color_mode`.
+    pub fn with_terminal_setup(mut writer: W) -> Result<Self> {
+        setup_terminal(&mut writer)?;
+        let mut target = Self::from_writer(writer);
+        target.terminal_setup = true;
+        target.synchronized_output = capabilities::supports_synchronized_output();
+        target.color_mode = capabilities::color_mode();
+        Ok(target)
+    }
+
+    /// Set the colours the terminal can show.
+    /// Other colours are replaced by the closest colour that can be shown.
+    ///
+    /// This is `ColorMode::TrueColor` unless the terminal was set up by the target.
+    pub fn set_color_mode(&mut self, color_mode: ColorMode) {
+        self.color_mode = color_mode;
+    }
+
+    /// The colours the terminal can show
+    pub fn color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+
+    /// Wrap every frame in the synchronized output escape codes
+    /// (DEC private mode 2026), so the terminal draws the whole frame at once
+    /// instead of tearing halfway through a large frame.
+    ///
+    /// Most terminals without support for it ignore the escape codes.
+    pub fn set_synchronized_output(&mut self, enabled: bool) {
+        self.synchronized_output = enabled;
+    }
+
+    /// True if frames are wrapped in the synchronized output escape codes
+    pub fn synchronized_output(&self) -> bool {
+        self.synchronized_output
+    }
+
+    /// Enable mouse capture, so `Event::Mouse` events are generated.
+    ///
+    /// Mouse capture is disabled again once this is dropped.
+    pub fn enable_mouse_capture(&mut self) -> Result<()> {
+        self.writer.execute(EnableMouseCapture)?;
+        self.mouse_capture = true;
+        if self.terminal_hooks {
+            terminal::set_mouse_capture(true);
+        }
+        Ok(())
+    }
+
+    /// Disable mouse capture.
+    ///
+    /// ! Mouse capture has to be enabled first, or this will crash on windows.
+    pub fn disable_mouse_capture(&mut self) -> Result<()> {
+        self.writer.execute(DisableMouseCapture)?;
+        self.mouse_capture = false;
+        if self.terminal_hooks {
+            terminal::set_mouse_capture(false);
+        }
+        Ok(())
+    }
+
+    fn queue_color(&mut self, color: Option<Color>, foreground: bool) -> Result<()> {
+        if self.color_mode == ColorMode::Ansi16 {
+            write!(self.writer, "\x1b[{}m", ansi16_sgr(color, foreground))?;
+            return Ok(());
+        }
+
+        let color = color.unwrap_or(Color::Reset);
+        match foreground {
+            true => self.writer.queue(SetForegroundColor(color))?,
+            false => self.writer.queue(SetBackgroundColor(color))?,
+        };
+        Ok(())
     }
-}
 
-impl RenderTarget for StdoutTarget {
-    fn render(&mut self, pixels: Vec<Pixel>) {
+    /// The writer
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// The writer, mutably
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    // Reset the text style, so the terminal matches the last style again
+    // after it was changed from the outside
+    fn reset_style(&mut self) -> Result<()> {
+        self.writer.queue(SetAttribute(Attribute::Reset))?;
+        self.last_color_fg = None;
+        self.last_color_bg = None;
+        self.last_attributes = Attributes::empty();
+        Ok(())
+    }
+
+    // Pixels next to each other on a row, with the same colours and attributes,
+    // are printed as one run, and the cursor is only moved when the next pixel
+    // isn't where the last glyph left the cursor.
+    fn queue_pixels(&mut self, pixels: Vec<Pixel>) -> Result<()> {
+        let mut cursor = None;
+        let mut run = String::new();
+
         for pixel in pixels {
-            self.stdout
-                .queue(MoveTo(pixel.pos.x, pixel.pos.y))
-                .expect("failed to move cursor");
+            // The wide glyph to the left already covers this cell
+            if pixel.glyph.is_continuation() {
+                continue;
+            }
+
+            let fg_color = pixel.fg_color.and_then(|color| self.color_mode.convert(color));
+            let bg_color = pixel.bg_color.and_then(|color| self.color_mode.convert(color));
+
+            let style_changed = self.last_attributes != pixel.attributes
+                || self.last_color_fg != fg_color
+                || self.last_color_bg != bg_color;
+
+            if (style_changed || cursor != Some(pixel.pos)) && !run.is_empty() {
+                self.writer.queue(Print(&run))?;
+                run.clear();
+            }
+
+            if cursor != Some(pixel.pos) {
+                self.writer.queue(MoveTo(pixel.pos.x, pixel.pos.y))?;
+            }
+
+            // Set the attributes if they are different than the last
+            // attributes used. Resetting the attributes also resets the colours.
+            if self.last_attributes != pixel.attributes {
+                self.last_attributes = pixel.attributes;
+                self.last_color_fg = None;
+                self.last_color_bg = None;
+                self.writer.queue(SetAttribute(Attribute::Reset))?;
+                for attribute in pixel.attributes.terminal_attributes() {
+                    self.writer.queue(SetAttribute(attribute))?;
+                }
+            }
 
             // Set the foreground colour if the colour is different
             // than the last colour used
-            if self.last_color_fg != pixel.fg_color {
-                self.last_color_fg = pixel.fg_color;
-                let _ = match self.last_color_fg {
-                    Some(color) => self.stdout.queue(SetForegroundColor(color)),
-                    None => self.stdout.queue(SetForegroundColor(Color::Reset)),
-                };
+            if self.last_color_fg != fg_color {
+                self.last_color_fg = fg_color;
+                self.queue_color(fg_color, true)?;
             }
 
             // Set the background colour if the colour is different
             // than the last colour used
-            if self.last_color_bg != pixel.bg_color {
-                self.last_color_bg = pixel.bg_color;
-                let _ = match self.last_color_bg {
-                    Some(color) => self.stdout.queue(SetBackgroundColor(color)),
-                    None => self.stdout.queue(SetBackgroundColor(Color::Reset)),
-                };
+            if self.last_color_bg != bg_color {
+                self.last_color_bg = bg_color;
+                self.queue_color(bg_color, false)?;
             }
 
-            self.stdout
-                .queue(Print(pixel.glyph.to_string()))
-                .expect("failed to print");
+            run.push_str(pixel.glyph.as_str());
+            let x = pixel.pos.x.saturating_add(pixel.glyph.width());
+            cursor = Some(ScreenPos::new(x, pixel.pos.y));
+        }
+
+        if !run.is_empty() {
+            self.writer.queue(Print(&run))?;
         }
 
-        let _ = self.stdout.flush();
+        Ok(())
     }
+}
+
+// Not available as commands in crossterm
+const BEGIN_SYNCHRONIZED_UPDATE: &[u8] = b"\x1b[?2026h";
+const END_SYNCHRONIZED_UPDATE: &[u8] = b"\x1b[?2026l";
+
+impl<W: Write> RenderTarget for AnsiTarget<W> {
+    fn render(&mut self, pixels: Vec<Pixel>) -> Result<()> {
+        let resumes = terminal::resumes();
+        if self.resumes != resumes {
+            self.resumes = resumes;
+            self.reset_style()?;
+        }
+
+        let synchronized = self.synchronized_output && !pixels.is_empty();
+        if synchronized {
+            self.writer.write_all(BEGIN_SYNCHRONIZED_UPDATE)?;
+        }
+
+        let mut result = self.queue_pixels(pixels);
+
+        // End the update even if the frame failed, or the terminal could stay frozen
+        if synchronized {
+            let end = self.writer.write_all(END_SYNCHRONIZED_UPDATE);
+            result = result.and(end.map_err(Error::from));
+        }
 
-    fn clear(&mut self) {
-        let _ = self.stdout.execute(Clear(ClearType::All));
+        let flushed = self.writer.flush();
+        result?;
+        flushed?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        // Otherwise the screen is cleared with the last background colour
+        self.reset_style()?;
+        self.writer.execute(Clear(ClearType::All))?;
+        Ok(())
     }
 }
 
-impl Drop for StdoutTarget {
+impl<W: Write> Drop for AnsiTarget<W> {
     fn drop(&mut self) {
-        let _ = reset_terminal_from_stdout_target(&mut self.stdout);
+        // The terminal might already be restored by the panic hook
+        if self.terminal_hooks && !terminal::deactivate() {
+            return;
+        }
+
+        if self.mouse_capture {
+            let _ = self.writer.execute(DisableMouseCapture);
+        }
+
+        if self.terminal_setup {
+            let _ = reset_terminal(&mut self.writer);
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Stdout render target -
+// -----------------------------------------------------------------------------
+/// Render to stdout
+pub type StdoutTarget = AnsiTarget<Stdout>;
+
+impl AnsiTarget<Stdout> {
+    /// Create a new stdout target.
+    /// This sets up the terminal so tinybit can draw on it. That includes:
+    /// * Enabling raw mode
+    /// * Entering an alternate screen
+    /// * Hiding the cursor
+    /// * Clearing the screen
+    ///
+    /// Once this is dropped it will reset all these settings.
+    /// The settings are also reset on panic and on some signals,
+    /// see the `terminal` module.
+    pub fn new() -> Result<Self> {
+        let mut target = Self::with_terminal_setup(io::stdout())?;
+        target.terminal_hooks = true;
+        terminal::activate();
+        Ok(target)
     }
 }
 
@@ -173,8 +425,142 @@ impl Drop for StdoutTarget {
 pub struct DummyTarget;
 
 impl RenderTarget for DummyTarget {
-    fn render(&mut self, _pixels: Vec<Pixel>) {}
-    fn clear(&mut self) {}
+    fn render(&mut self, _pixels: Vec<Pixel>) -> Result<()> {
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Memory render target -
+// -----------------------------------------------------------------------------
+/// Render to an in-memory screen.
+///
+/// Every call to `render` is applied on top of the previous frame,
+/// the same way a terminal would apply it, which makes it possible
+/// to test rendering without a terminal.
+///
+/// ```
+/// # use tinybit::*;
+/// let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(3, 1));
+/// let mut renderer = Renderer::new(MemoryTarget::new(ScreenSize::new(3, 1)));
+/// viewport.draw_pixel(Pixel::white('@', ScreenPos::new(1, 0)));
+/// renderer.render(&mut viewport).unwrap();
+/// assert_eq!(renderer.target().to_string(), " @ ");
+/// ```
+#[derive(Debug)]
+pub struct MemoryTarget {
+    buffer: PixelBuffer,
+    size: ScreenSize,
+}
+
+impl MemoryTarget {
+    /// Create a new memory target with a given screen size.
+    pub fn new(size: ScreenSize) -> Self {
+        Self {
+            buffer: PixelBuffer::new(size),
+            size,
+        }
+    }
+
+    /// The size of the screen.
+    pub fn size(&self) -> ScreenSize {
+        self.size
+    }
+
+    /// Resize the screen.
+    /// This clears the current frame.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.size = ScreenSize::new(width, height);
+        self.buffer = PixelBuffer::new(self.size);
+    }
+
+    /// Get the pixel at the given screen position, if anything
+    /// has been drawn there.
+    pub fn pixel(&self, pos: ScreenPos) -> Option<Pixel> {
+        if !self.on_screen(pos) {
+            return None;
+        }
+        self.buffer.get_pixel(pos)
+    }
+
+    /// The current frame as lines of text.
+    /// Cells without a pixel are represented by a space.
+    /// Wide glyphs cover two cells, so the cell to the right of a wide glyph is skipped.
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.size.height)
+            .map(|y| {
+                (0..self.size.width).fold(String::new(), |mut line, x| {
+                    match self.buffer.get_pixel(ScreenPos::new(x, y)) {
+                        Some(pixel) => line.push_str(pixel.glyph.as_str()),
+                        None => line.push(' '),
+                    }
+                    line
+                })
+            })
+            .collect()
+    }
+
+    /// Compare the current frame against a golden file.
+    ///
+    /// If the file doesn't exist, or the `TINYBIT_UPDATE_SNAPSHOTS`
+    /// environment variable is set, the file is (re)written with the current frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame doesn't match the content of the file,
+    /// or if the file can't be read or written.
+    pub fn assert_snapshot(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let frame = self.to_string();
+
+        if env::var_os("TINYBIT_UPDATE_SNAPSHOTS").is_some() || !path.exists() {
+            fs::write(path, &frame)
+                .unwrap_or_else(|e| panic!("failed to write snapshot {}: {}", path.display(), e));
+            return;
+        }
+
+        let expected = fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("failed to read snapshot {}: {}", path.display(), e));
+
+        if expected != frame {
+            panic!(
+                "frame does not match snapshot {}\n\nexpected:\n{}\n\nfound:\n{}\n",
+                path.display(),
+                expected,
+                frame
+            );
+        }
+    }
+
+    fn on_screen(&self, pos: ScreenPos) -> bool {
+        pos.x < self.size.width && pos.y < self.size.height
+    }
+}
+
+impl fmt::Display for MemoryTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lines().join("\n"))
+    }
+}
+
+impl RenderTarget for MemoryTarget {
+    fn render(&mut self, pixels: Vec<Pixel>) -> Result<()> {
+        for pixel in pixels {
+            if self.on_screen(pixel.pos) {
+                self.buffer.set_pixel(pixel);
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.buffer = PixelBuffer::new(self.size);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -199,11 +585,14 @@ mod test {
     }
 
     impl RenderTarget for DummyTarget {
-        fn render(&mut self, pixels: Vec<Pixel>) {
+        fn render(&mut self, pixels: Vec<Pixel>) -> error::Result<()> {
             self.pixels = pixels;
+            Ok(())
         }
 
-        fn clear(&mut self) {}
+        fn clear(&mut self) -> error::Result<()> {
+            Ok(())
+        }
     }
 
     #[test]
@@ -215,15 +604,234 @@ mod test {
         let min_y = cam.bounding_box.min_y();
 
         let a = ('A', WorldPos::new(min_x, min_y));
-        let a = Pixel::new(a.0, cam.to_screen(a.1), None, None);
+        let a = Pixel::new(a.0, cam.to_screen(a.1).unwrap(), None, None);
 
         view.draw_pixel(a);
         let mut renderer = Renderer::new(DummyTarget { pixels: Vec::new() });
 
-        renderer.render(&mut view);
+        renderer.render(&mut view).unwrap();
 
         let a = Pixel::new('A', ScreenPos::new(2, 2), None, None); // 2, 2 because of the viewport offset
         let pixels = vec![a];
         assert_eq!(pixels, renderer.target.pixels);
     }
+
+    #[test]
+    fn memory_target_applies_frames() {
+        let mut view = viewport();
+        let mut renderer = Renderer::new(MemoryTarget::new(ScreenSize::new(8, 3)));
+
+        view.draw_pixel(Pixel::white('A', ScreenPos::new(0, 0)));
+        view.draw_pixel(Pixel::white('B', ScreenPos::new(1, 0)));
+        renderer.render(&mut view).unwrap();
+        assert_eq!(renderer.target().lines()[2], "  AB    ");
+
+        // Only `B` is drawn in the second frame, so `A` is cleared
+        view.draw_pixel(Pixel::white('B', ScreenPos::new(1, 0)));
+        renderer.render(&mut view).unwrap();
+        assert_eq!(renderer.target().lines()[2], "   B    ");
+        assert_eq!(
+            renderer.target().pixel(ScreenPos::new(3, 2)),
+            Some(Pixel::white('B', ScreenPos::new(3, 2)))
+        );
+    }
+
+    #[test]
+    fn memory_target_snapshot() {
+        let path = std::env::temp_dir().join("tinybit_memory_target_snapshot.txt");
+        let _ = std::fs::remove_file(&path);
+
+        let mut target = MemoryTarget::new(ScreenSize::new(2, 2));
+        target.render(vec![Pixel::white('x', ScreenPos::new(1, 1))]).unwrap();
+
+        // First run writes the snapshot, second run compares against it
+        target.assert_snapshot(&path);
+        target.assert_snapshot(&path);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "  \n x");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn ansi_target_output() {
+        let mut target = AnsiTarget::from_writer(Vec::new());
+        let pixels = vec![
+            Pixel::new('A', ScreenPos::new(0, 0), Some(Color::Red), None),
+            Pixel::new('B', ScreenPos::new(4, 1), Some(Color::Red), None),
+        ];
+        target.render(pixels).unwrap();
+
+        let output = String::from_utf8(target.writer().clone()).unwrap();
+        // Cursor positions are 1 based
+        assert_eq!(output, "\x1b[1;1H\x1b[38;5;9mA\x1b[2;5HB");
+    }
+
+    #[test]
+    fn ansi_target_write_error() {
+        struct Closed;
+
+        impl Write for Closed {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        let mut target = AnsiTarget::from_writer(Closed);
+        let err = target
+            .render(vec![Pixel::white('A', ScreenPos::zero())])
+            .unwrap_err();
+        assert_eq!(err.io_error().map(|e| e.kind()), Some(io::ErrorKind::BrokenPipe));
+    }
+
+    #[test]
+    fn ansi_target_attributes() {
+        let mut target = AnsiTarget::from_writer(Vec::new());
+        let pixels = vec![
+            Pixel::new('A', ScreenPos::new(0, 0), Some(Color::Red), None)
+                .with_attributes(Attributes::BOLD | Attributes::REVERSE),
+            Pixel::new('B', ScreenPos::new(1, 0), Some(Color::Red), None),
+        ];
+        target.render(pixels).unwrap();
+
+        // The colour has to be set again after the attributes are reset
+        let output = String::from_utf8(target.writer().clone()).unwrap();
+        assert_eq!(
+            output,
+            "\x1b[1;1H\x1b[0m\x1b[1m\x1b[7m\x1b[38;5;9mA\x1b[0m\x1b[38;5;9mB"
+        );
+    }
+
+    #[test]
+    fn ansi_target_wide_glyph() {
+        let mut target = AnsiTarget::from_writer(Vec::new());
+        let mut buffer = PixelBuffer::new(ScreenSize::new(3, 1));
+        buffer.set_pixel(Pixel::white('中', ScreenPos::new(0, 0)));
+        buffer.set_pixel(Pixel::white('a', ScreenPos::new(2, 0)));
+        target.render(buffer.pixels.iter().flatten().copied().collect()).unwrap();
+
+        let output = String::from_utf8(target.writer().clone()).unwrap();
+        assert_eq!(output, "\x1b[1;1H中a");
+    }
+
+    #[test]
+    fn ansi_target_synchronized_output() {
+        let mut target = AnsiTarget::from_writer(Vec::new());
+        assert!(!target.synchronized_output());
+        target.set_synchronized_output(true);
+        target.render(vec![Pixel::white('A', ScreenPos::zero())]).unwrap();
+        target.render(vec![]).unwrap();
+
+        let output = String::from_utf8(target.writer().clone()).unwrap();
+        assert_eq!(output, "\x1b[?2026h\x1b[1;1HA\x1b[?2026l");
+    }
+
+    #[test]
+    fn ansi_target_synchronized_output_error() {
+        // Fails to print the glyph `B`
+        struct Flaky(Vec<u8>);
+
+        impl Write for Flaky {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                match buf.contains(&b'B') {
+                    true => Err(io::ErrorKind::BrokenPipe.into()),
+                    false => self.0.write(buf),
+                }
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut target = AnsiTarget::from_writer(Flaky(Vec::new()));
+        target.set_synchronized_output(true);
+        let pixels = vec![
+            Pixel::white('A', ScreenPos::zero()),
+            Pixel::white('B', ScreenPos::new(3, 3)),
+        ];
+        assert!(target.render(pixels).is_err());
+
+        // The update is still ended
+        let output = String::from_utf8(target.writer().0.clone()).unwrap();
+        assert_eq!(output, "\x1b[?2026h\x1b[1;1HA\x1b[4;4H\x1b[?2026l");
+    }
+
+    #[test]
+    fn ansi_target_style_after_resume() {
+        let pixel = Pixel::new('A', ScreenPos::new(0, 0), Some(Color::Red), None).with_attributes(Attributes::BOLD);
+        let mut target = AnsiTarget::from_writer(Vec::new());
+        target.render(vec![pixel]).unwrap();
+        let first = String::from_utf8(target.writer().clone()).unwrap();
+        assert_eq!(first, "\x1b[1;1H\x1b[0m\x1b[1m\x1b[38;5;9mA");
+
+        // Same style, so only the glyph is printed
+        target.writer_mut().clear();
+        target.render(vec![pixel]).unwrap();
+        assert_eq!(target.writer(), b"\x1b[1;1HA");
+
+        // Simulate a resume: the style is reset and set again
+        target.writer_mut().clear();
+        target.resumes = terminal::resumes().wrapping_add(1);
+        target.render(vec![pixel]).unwrap();
+        let resumed = String::from_utf8(target.writer().clone()).unwrap();
+        assert_eq!(resumed, format!("\x1b[0m{}", first));
+
+        // The same after clearing the screen
+        target.writer_mut().clear();
+        target.clear().unwrap();
+        target.render(vec![pixel]).unwrap();
+        let cleared = String::from_utf8(target.writer().clone()).unwrap();
+        assert_eq!(cleared, format!("\x1b[0m\x1b[2J{}", first));
+    }
+
+    #[test]
+    fn ansi_target_color_modes() {
+        let pixels = vec![
+            Pixel::new('A', ScreenPos::new(0, 0), Some(Color::Rgb { r: 250, g: 0, b: 0 }), Some(Color::DarkBlue)),
+            Pixel::new('B', ScreenPos::new(1, 0), None, None),
+        ];
+        let output = |mode| {
+            let mut target = AnsiTarget::from_writer(Vec::new());
+            target.set_color_mode(mode);
+            target.render(pixels.clone()).unwrap();
+            String::from_utf8(target.writer().clone()).unwrap()
+        };
+
+        assert_eq!(output(ColorMode::Ansi256), "\x1b[1;1H\x1b[38;5;196m\x1b[48;5;4mA\x1b[39m\x1b[49mB");
+        assert_eq!(output(ColorMode::Ansi16), "\x1b[1;1H\x1b[91m\x1b[44mA\x1b[39m\x1b[49mB");
+        assert_eq!(output(ColorMode::Monochrome), "\x1b[1;1HAB");
+    }
+
+    #[test]
+    fn ansi_target_runs() {
+        let mut target = AnsiTarget::from_writer(Vec::new());
+        let pixels = vec![
+            Pixel::white('A', ScreenPos::new(0, 0)),
+            Pixel::white('B', ScreenPos::new(1, 0)),
+            Pixel::white('C', ScreenPos::new(2, 0)),
+            Pixel::new('D', ScreenPos::new(3, 0), Some(Color::Red), None),
+            Pixel::new('E', ScreenPos::new(5, 0), Some(Color::Red), None),
+            Pixel::new('F', ScreenPos::new(0, 1), Some(Color::Red), None),
+        ];
+        target.render(pixels).unwrap();
+
+        let output = String::from_utf8(target.writer().clone()).unwrap();
+        assert_eq!(
+            output,
+            "\x1b[1;1HABC\x1b[38;5;9mD\x1b[1;6HE\x1b[2;1HF"
+        );
+    }
+
+    #[test]
+    fn memory_target_wide_glyph() {
+        let mut view = viewport();
+        let mut renderer = Renderer::new(MemoryTarget::new(ScreenSize::new(8, 3)));
+
+        view.draw_widget(&widgets::Text::new("中a", None, None), ScreenPos::zero());
+        renderer.render(&mut view).unwrap();
+        assert_eq!(renderer.target().lines()[2], "  中a   ");
+    }
 }
@@ -0,0 +1,327 @@
+//! Restore the terminal when the game doesn't exit normally.
+//!
+//! Once a `StdoutTarget` has set up the terminal (see `StdoutTarget::new`),
+//! tinybit restores the terminal:
+//! * on panic, before the panic message is printed, even with `panic = "abort"`
+//! * on SIGTERM, before the process is terminated
+//! * on SIGTSTP, before suspending. Once resumed (SIGCONT) the terminal
+//!   is set up again and an `Event::Redraw` is sent from the `TerminalSource`.
+//!   Call `Viewport::invalidate` when it arrives, so everything is drawn again.
+//!   The target sets the colours and attributes again on the next render.
+//!
+//! The terminal is restored by writing to stdout, so this only applies
+//! to the stdout target, not to targets created with
+//! `AnsiTarget::with_terminal_setup`.
+//!
+//! While the terminal isn't set up, SIGTERM terminates the process as usual.
+//! If SIGTERM already has a handler when the terminal is first set up,
+//! tinybit leaves SIGTERM alone.
+//!
+//! In raw mode the terminal sends Ctrl+Z as a key event instead of suspending
+//! the process. Call `suspend` to suspend the game:
+//!
+//! ```no_run
+//! # use tinybit::events::{events, Event, EventModel, KeyCode, KeyModifiers};
+//! for event in events(EventModel::Fps(20)) {
+//!     if let Event::Key(key) = event {
+//!         if key.code == KeyCode::Char('z') && key.modifiers == KeyModifiers::CONTROL {
+//!             tinybit::terminal::suspend();
+//!         }
+//!     }
+//! }
+//! ```
+use std::io::{self, Write};
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Once;
+
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::ExecutableCommand;
+
+use crate::error::Result;
+use crate::render::{reset_terminal, setup_terminal};
+
+static STATE: State = State::new();
+static INSTALL: Once = Once::new();
+
+/// Suspend the process, the same way Ctrl+Z would outside of raw mode.
+/// The terminal is restored while suspended.
+///
+/// This does nothing on platforms without job control.
+pub fn suspend() {
+    #[cfg(unix)]
+    unsafe {
+        libc::raise(libc::SIGTSTP);
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - State -
+// -----------------------------------------------------------------------------
+// The state of the terminal, shared by the stdout target,
+// the panic hook and the signal handlers
+struct State {
+    // Set while the terminal is set up
+    active: AtomicBool,
+    mouse_capture: AtomicBool,
+    // Set while the terminal is restored because the process is suspended
+    suspended: AtomicBool,
+    // Set when the screen has to be drawn again, e.g after resuming
+    redraw: AtomicBool,
+    // The number of times the process was resumed while the terminal was set up
+    resumes: AtomicUsize,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            mouse_capture: AtomicBool::new(false),
+            suspended: AtomicBool::new(false),
+            redraw: AtomicBool::new(false),
+            resumes: AtomicUsize::new(0),
+        }
+    }
+
+    fn activate(&self) {
+        self.active.store(true, Ordering::SeqCst);
+    }
+
+    // The target resets the terminal itself.
+    // Returns false if the terminal was already restored, e.g by the panic hook.
+    fn deactivate(&self) -> bool {
+        self.mouse_capture.store(false, Ordering::SeqCst);
+        self.suspended.store(false, Ordering::SeqCst);
+        self.active.swap(false, Ordering::SeqCst)
+    }
+
+    // Returns true if the terminal is set up and has to be restored
+    fn restore(&self) -> bool {
+        self.active.swap(false, Ordering::SeqCst)
+    }
+
+    // On SIGTSTP.
+    // Returns true if the terminal is set up and has to be restored.
+    fn suspend(&self) -> bool {
+        let restore = self.restore();
+        if restore {
+            self.suspended.store(true, Ordering::SeqCst);
+        }
+        restore
+    }
+
+    // On SIGCONT.
+    // Returns true if the terminal was restored by `suspend` and has to be set up again.
+    // Otherwise the process was stopped by something else (e.g SIGSTOP),
+    // which can still leave the screen in a mess, so it's drawn again.
+    fn resume(&self) -> bool {
+        if self.suspended.swap(false, Ordering::SeqCst) {
+            return true;
+        }
+
+        if self.active.load(Ordering::SeqCst) {
+            self.resumes.fetch_add(1, Ordering::SeqCst);
+            self.redraw.store(true, Ordering::SeqCst);
+        }
+        false
+    }
+
+    // The terminal was set up again after `resume`
+    fn resumed(&self) {
+        self.active.store(true, Ordering::SeqCst);
+        self.resumes.fetch_add(1, Ordering::SeqCst);
+        self.redraw.store(true, Ordering::SeqCst);
+    }
+
+    fn take_redraw(&self) -> bool {
+        self.redraw.swap(false, Ordering::SeqCst)
+    }
+}
+
+// Called when the stdout target sets up the terminal.
+// This installs the panic hook and signal handlers the first time.
+pub(crate) fn activate() {
+    STATE.activate();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            previous(info);
+        }));
+
+        #[cfg(unix)]
+        signals::spawn();
+    });
+}
+
+// Called when the stdout target resets the terminal.
+// Returns false if the terminal was already restored, e.g by the panic hook.
+pub(crate) fn deactivate() -> bool {
+    STATE.deactivate()
+}
+
+pub(crate) fn set_mouse_capture(enabled: bool) {
+    STATE.mouse_capture.store(enabled, Ordering::SeqCst);
+}
+
+// True once after the screen has to be drawn again
+pub(crate) fn take_redraw() -> bool {
+    STATE.take_redraw()
+}
+
+// Changes every time the process is resumed.
+// The shell resets the text style while the process is suspended,
+// so the stdout target can't rely on the style it last set.
+pub(crate) fn resumes() -> usize {
+    STATE.resumes.load(Ordering::SeqCst)
+}
+
+// Restore the terminal, if it's set up
+fn restore() {
+    if STATE.restore() {
+        reset_stdout();
+    }
+}
+
+// The stdout target is out of reach, so this writes to stdout directly
+fn reset_stdout() {
+    let mut stdout = io::stdout();
+    if STATE.mouse_capture.load(Ordering::SeqCst) {
+        let _ = stdout.execute(DisableMouseCapture);
+    }
+    let _ = reset_terminal(&mut stdout);
+    let _ = stdout.flush();
+}
+
+// Set the terminal up again after `reset_stdout`
+fn setup_stdout() -> Result<()> {
+    let mut stdout = io::stdout();
+    setup_terminal(&mut stdout)?;
+    if STATE.mouse_capture.load(Ordering::SeqCst) {
+        stdout.execute(EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+mod signals {
+    use std::mem;
+    use std::ptr;
+    use std::thread;
+
+    use libc::{c_int, SIGCONT, SIGSTOP, SIGTERM, SIGTSTP, SIG_DFL};
+    use signal_hook::iterator::Signals;
+
+    use super::{reset_stdout, restore, setup_stdout, STATE};
+
+    pub(super) fn spawn() {
+        let mut handled = vec![SIGTSTP, SIGCONT];
+        // Don't replace the application's own handler
+        if has_default_action(SIGTERM) {
+            handled.push(SIGTERM);
+        }
+
+        let signals = match Signals::new(handled) {
+            Ok(signals) => signals,
+            Err(_) => return,
+        };
+
+        thread::spawn(move || {
+            for signal in signals.forever() {
+                match signal {
+                    SIGTSTP => {
+                        if STATE.suspend() {
+                            reset_stdout();
+                        }
+                        // The handler replaced the default action, so stop the process
+                        unsafe {
+                            libc::raise(SIGSTOP);
+                        }
+                    }
+                    SIGCONT if STATE.resume() && setup_stdout().is_ok() => STATE.resumed(),
+                    SIGTERM => {
+                        restore();
+                        raise_default(SIGTERM);
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    fn has_default_action(signal: c_int) -> bool {
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            libc::sigaction(signal, ptr::null(), &mut action) == 0 && action.sa_sigaction == SIG_DFL
+        }
+    }
+
+    // Raise the signal again with the default action,
+    // as if the handler was never installed
+    fn raise_default(signal: c_int) {
+        unsafe {
+            libc::signal(signal, SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn restore_once() {
+        let state = State::new();
+        assert!(!state.restore());
+
+        state.activate();
+        assert!(state.restore());
+        // Already restored, e.g by the panic hook, so the target doesn't reset it again
+        assert!(!state.deactivate());
+
+        state.activate();
+        state.mouse_capture.store(true, Ordering::SeqCst);
+        assert!(state.deactivate());
+        assert!(!state.mouse_capture.load(Ordering::SeqCst));
+        assert!(!state.restore());
+    }
+
+    #[test]
+    fn suspend_and_resume() {
+        let state = State::new();
+        state.activate();
+
+        assert!(state.suspend());
+        assert!(!state.active.load(Ordering::SeqCst));
+        assert!(!state.take_redraw());
+
+        assert!(state.resume());
+        state.resumed();
+        assert!(state.active.load(Ordering::SeqCst));
+        assert!(state.take_redraw());
+        assert!(!state.take_redraw());
+        assert_eq!(state.resumes.load(Ordering::SeqCst), 1);
+
+        // Stopped by something other than SIGTSTP, so only redraw
+        assert!(!state.resume());
+        assert!(state.take_redraw());
+        assert_eq!(state.resumes.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn suspend_without_terminal() {
+        let state = State::new();
+        assert!(!state.suspend());
+        assert!(!state.resume());
+        assert!(!state.take_redraw());
+
+        // The target was dropped while suspended
+        state.activate();
+        assert!(state.suspend());
+        state.deactivate();
+        assert!(!state.resume());
+        assert!(!state.take_redraw());
+        assert_eq!(state.resumes.load(Ordering::SeqCst), 0);
+    }
+}
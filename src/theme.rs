@@ -0,0 +1,277 @@
+//! Named colours and styles.
+//!
+//! A theme gives the colours used in a game names, like "wall" or "ui.border",
+//! so the look of the game can be changed in one place, or loaded from a file:
+//!
+//! ```
+//! # use tinybit::*;
+//! # use tinybit::theme::Theme;
+//! let theme = Theme::from_json(r##"{
+//!     "palette": { "stone": "#7f7f7f", "gold": "#ffd700" },
+//!     "styles": {
+//!         "wall": { "fg": "stone" },
+//!         "player": { "fg": "gold", "attributes": ["bold"] },
+//!         "ui": { "fg": "white", "bg": "dark_blue" },
+//!         "ui.focus": { "fg": "black", "bg": "gold" }
+//!     }
+//! }"##).unwrap();
+//!
+//! let wall = theme.style("wall").pixel('#', ScreenPos::zero());
+//! assert_eq!(wall.fg_color, Some(Color::Rgb { r: 127, g: 127, b: 127 }));
+//!
+//! // "ui.border" isn't in the theme, so the "ui" style is used
+//! assert_eq!(theme.style("ui.border"), theme.style("ui"));
+//! ```
+//!
+//! Colours are either a name from the palette, a hex colour (`"#rrggbb"`),
+//! one of the 16 named colours (`"red"`, `"dark_red"`, ...)
+//! or an index in the 256 colour palette (`"208"`).
+//!
+//! The widgets use the following styles when created with their `themed` constructors:
+//! * `ui.text`: text and text fields
+//! * `ui.border`: borders
+//! * `ui.focus`: the cursor of a focused text field
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::Result;
+use crate::{Attributes, Color, Glyph, Pixel, ScreenPos};
+
+// -----------------------------------------------------------------------------
+//     - Style -
+// -----------------------------------------------------------------------------
+/// Colours and attributes to draw with.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Style {
+    /// Foreground colour
+    pub fg_color: Option<Color>,
+    /// Background colour
+    pub bg_color: Option<Color>,
+    /// Text attributes
+    pub attributes: Attributes,
+}
+
+impl Style {
+    /// Create a new style without any attributes
+    pub fn new(fg_color: Option<Color>, bg_color: Option<Color>) -> Self {
+        Self {
+            fg_color,
+            bg_color,
+            attributes: Attributes::empty(),
+        }
+    }
+
+    /// The same style, with the given attributes
+    pub fn with_attributes(self, attributes: Attributes) -> Self {
+        Self { attributes, ..self }
+    }
+
+    /// Create a pixel with this style
+    pub fn pixel(&self, glyph: impl Into<Glyph>, pos: ScreenPos) -> Pixel {
+        Pixel::new(glyph, pos, self.fg_color, self.bg_color).with_attributes(self.attributes)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Theme -
+// -----------------------------------------------------------------------------
+/// Named colours (the palette) and named styles.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Theme {
+    palette: HashMap<String, Color>,
+    styles: HashMap<String, Style>,
+}
+
+impl Theme {
+    /// Create an empty theme
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a theme from a json file.
+    /// See the module documentation for the format.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Load a theme from json.
+    /// See the module documentation for the format.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: ThemeFile = serde_json::from_str(json)?;
+        let theme = file.into_theme().map_err(<serde_json::Error as serde::de::Error>::custom)?;
+        Ok(theme)
+    }
+
+    /// Add a named colour to the palette
+    pub fn set_color(&mut self, name: impl Into<String>, color: Color) {
+        self.palette.insert(name.into(), color);
+    }
+
+    /// A named colour from the palette
+    pub fn color(&self, name: &str) -> Option<Color> {
+        self.palette.get(name).copied()
+    }
+
+    /// Add a named style
+    pub fn set_style(&mut self, name: impl Into<String>, style: Style) {
+        self.styles.insert(name.into(), style);
+    }
+
+    /// A named style.
+    ///
+    /// If there is no style with the name, the name is shortened
+    /// to the last `.` until there is: "ui.border.focus", "ui.border", "ui".
+    /// If there is still no style the default style is returned.
+    pub fn style(&self, name: &str) -> Style {
+        let mut name = name;
+        loop {
+            if let Some(style) = self.styles.get(name) {
+                return *style;
+            }
+
+            match name.rfind('.') {
+                Some(i) => name = &name[..i],
+                None => return Style::default(),
+            }
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Theme file -
+// -----------------------------------------------------------------------------
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    #[serde(default)]
+    palette: HashMap<String, String>,
+    #[serde(default)]
+    styles: HashMap<String, StyleFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StyleFile {
+    fg: Option<String>,
+    bg: Option<String>,
+    #[serde(default)]
+    attributes: Vec<String>,
+}
+
+impl ThemeFile {
+    fn into_theme(self) -> std::result::Result<Theme, String> {
+        let mut theme = Theme::new();
+
+        for (name, color) in &self.palette {
+            theme.set_color(name.clone(), parse_color(color)?);
+        }
+
+        for (name, style) in self.styles {
+            let color = |color: Option<String>| match color {
+                Some(color) => theme.color(&color).map(Ok).unwrap_or_else(|| parse_color(&color)).map(Some),
+                None => Ok(None),
+            };
+
+            let mut attributes = Attributes::empty();
+            for attribute in &style.attributes {
+                attributes.insert(parse_attribute(attribute)?);
+            }
+
+            let style = Style::new(color(style.fg)?, color(style.bg)?).with_attributes(attributes);
+            theme.set_style(name, style);
+        }
+
+        Ok(theme)
+    }
+}
+
+fn parse_color(s: &str) -> std::result::Result<Color, String> {
+    let error = || format!("invalid colour: \"{}\"", s);
+
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb { r, g, b }),
+            _ => Err(error()),
+        };
+    }
+
+    if let Ok(index) = s.parse() {
+        return Ok(Color::AnsiValue(index));
+    }
+
+    Color::try_from(s).map_err(|_| error())
+}
+
+fn parse_attribute(s: &str) -> std::result::Result<Attributes, String> {
+    match s {
+        "bold" => Ok(Attributes::BOLD),
+        "dim" => Ok(Attributes::DIM),
+        "italic" => Ok(Attributes::ITALIC),
+        "underline" => Ok(Attributes::UNDERLINE),
+        "blink" => Ok(Attributes::BLINK),
+        "reverse" => Ok(Attributes::REVERSE),
+        _ => Err(format!("invalid attribute: \"{}\"", s)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn load_theme() {
+        let theme = Theme::from_json(
+            r##"{
+                "palette": { "stone": "#7F7F7F", "red": "#ff0000" },
+                "styles": {
+                    "wall": { "fg": "stone", "bg": "236" },
+                    "enemy": { "fg": "red", "attributes": ["bold", "underline"] },
+                    "water": { "bg": "dark_blue" }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let grey = Color::Rgb { r: 127, g: 127, b: 127 };
+        assert_eq!(theme.color("stone"), Some(grey));
+        assert_eq!(theme.style("wall"), Style::new(Some(grey), Some(Color::AnsiValue(236))));
+
+        // The palette takes precedence over the named colours
+        let red = Color::Rgb { r: 255, g: 0, b: 0 };
+        let enemy = Style::new(Some(red), None).with_attributes(Attributes::BOLD | Attributes::UNDERLINE);
+        assert_eq!(theme.style("enemy"), enemy);
+        assert_eq!(theme.style("water"), Style::new(None, Some(Color::DarkBlue)));
+        assert_eq!(theme.style("lava"), Style::default());
+    }
+
+    #[test]
+    fn invalid_theme() {
+        assert!(Theme::from_json(r##"{ "palette": { "a": "#12345" } }"##).is_err());
+        assert!(Theme::from_json(r#"{ "styles": { "a": { "fg": "reddish" } } }"#).is_err());
+        assert!(Theme::from_json(r#"{ "styles": { "a": { "attributes": ["loud"] } } }"#).is_err());
+        assert!(Theme::from_json(r#"{ "colours": {} }"#).is_err());
+
+        // Bad data, rather than failing to read
+        let error = Theme::from_json(r#"{ "styles": { "a": { "fg": "reddish" } } }"#).unwrap_err();
+        assert!(matches!(error, crate::Error::Json(_)));
+    }
+
+    #[test]
+    fn style_fallback() {
+        let mut theme = Theme::new();
+        let ui = Style::new(Some(Color::White), None);
+        let focus = Style::new(Some(Color::Black), Some(Color::Yellow));
+        theme.set_style("ui", ui);
+        theme.set_style("ui.focus", focus);
+
+        assert_eq!(theme.style("ui.focus"), focus);
+        assert_eq!(theme.style("ui.focus.cursor"), focus);
+        assert_eq!(theme.style("ui.border"), ui);
+        assert_eq!(theme.style("uix"), Style::default());
+    }
+}
@@ -1,9 +1,44 @@
 use std::mem::swap;
 
+use crate::theme::Style;
 use crate::widgets::Widget;
-use crate::{Pixel, PixelBuffer, ScreenPos, ScreenSize};
+use crate::{Glyph, Pixel, PixelBuffer, ScreenPos, ScreenRect, ScreenSize};
+
+/// A layer of pixels in a viewport.
+/// Layers with a higher number are drawn on top of layers with a lower number.
+struct Layer {
+    index: u8,
+    buf: PixelBuffer,
+    retain: bool,
+}
+
+impl Layer {
+    fn new(index: u8, size: ScreenSize) -> Self {
+        Self {
+            index,
+            buf: PixelBuffer::new(size),
+            retain: false,
+        }
+    }
+}
 
 /// Represents a drawable area on screen.
+///
+/// A viewport has one or more numbered layers that are composited when rendered.
+/// Everything is drawn to layer 0 unless another layer is selected with `set_layer`.
+/// A pixel without a background colour lets the background of the layers below show through.
+///
+/// ```
+/// # use tinybit::*;
+/// let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(10, 10));
+///
+/// // Terrain
+/// viewport.draw_pixel(Pixel::new('.', ScreenPos::new(1, 1), None, Some(Color::Green)));
+///
+/// // The player is drawn on top of the terrain, keeping the green background
+/// viewport.set_layer(1);
+/// viewport.draw_pixel(Pixel::new('@', ScreenPos::new(1, 1), Some(Color::White), None));
+/// ```
 pub struct Viewport {
     /// The viewport's position on screen.
     /// Where 0,0 is the top left corner
@@ -12,8 +47,11 @@ pub struct Viewport {
     /// The size of the viewport. Should probably match the size of the camera
     /// that is used with this viewport.
     pub size: ScreenSize,
+    layers: Vec<Layer>,
+    active_layer: usize,
     new_buf: PixelBuffer,
     old_buf: PixelBuffer,
+    invalidated: bool,
 }
 
 impl Viewport {
@@ -22,18 +60,78 @@ impl Viewport {
         Self {
             position,
             size,
+            layers: vec![Layer::new(0, size)],
+            active_layer: 0,
             new_buf: PixelBuffer::new(size),
             old_buf: PixelBuffer::new(size),
+            invalidated: false,
         }
     }
 
     /// Resize the viewport.
-    /// Remember to clear the renderer or residual
-    /// characters might remain.
+    /// Retained layers keep the pixels that still fit.
+    ///
+    /// This invalidates the viewport, so the whole viewport is drawn on the next render.
     pub fn resize(&mut self, width: u16, height: u16) {
         self.size = ScreenSize::new(width, height);
+        for layer in &mut self.layers {
+            layer.buf.resize(self.size);
+        }
         self.new_buf = PixelBuffer::new(self.size);
         self.old_buf = PixelBuffer::new(self.size);
+        self.invalidate();
+    }
+
+    /// Draw every cell on the next render, not only the cells that changed.
+    /// Cells without a pixel are drawn as a blank space.
+    ///
+    /// Use this when the screen no longer shows the last frame,
+    /// e.g after clearing the renderer.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    /// Select the layer to draw on.
+    /// The layer is created if it doesn't exist.
+    pub fn set_layer(&mut self, layer: u8) {
+        self.active_layer = self.layer_index(layer);
+    }
+
+    /// The layer that is currently drawn on.
+    pub fn layer(&self) -> u8 {
+        self.layers[self.active_layer].index
+    }
+
+    /// Clear all pixels on a layer.
+    pub fn clear_layer(&mut self, layer: u8) {
+        let index = self.layer_index(layer);
+        self.layers[index].buf.clear();
+    }
+
+    /// By default all layers are cleared once they are rendered.
+    /// A retained layer keeps its pixels between renders
+    /// until it's cleared with `clear_layer`.
+    ///
+    /// This is useful for things that rarely change, like terrain.
+    pub fn retain_layer(&mut self, layer: u8, retain: bool) {
+        let index = self.layer_index(layer);
+        self.layers[index].retain = retain;
+    }
+
+    // Find the layer, or create it if it doesn't exist.
+    // Layers are kept sorted from the bottom to the top.
+    fn layer_index(&mut self, layer: u8) -> usize {
+        match self.layers.binary_search_by_key(&layer, |l| l.index) {
+            Ok(index) => index,
+            Err(index) => {
+                let active = self.layers[self.active_layer].index;
+                self.layers.insert(index, Layer::new(layer, self.size));
+                if active > layer {
+                    self.active_layer += 1;
+                }
+                index
+            }
+        }
     }
 
     /// Draw the pixels onto the renderable surface layers.
@@ -46,65 +144,322 @@ impl Viewport {
 
     /// Draw a single pixel onto the rendereable surface layers.
     /// This is called from `draw_pixels` for each pixel.
+    /// Pixels outside of the viewport are clipped.
     ///
     /// This is useful if it's desired to draw just one pixel.
     pub fn draw_pixel(&mut self, pixel: Pixel) {
         if self.in_view(pixel.pos) {
-            self.new_buf.set_pixel(pixel);
+            self.layers[self.active_layer].buf.set_pixel(pixel);
         }
     }
 
     /// Draw a widget with an offset in the viewport.
+    /// Anything outside of the viewport is clipped.
     pub fn draw_widget(&mut self, widget: &impl Widget, offset: ScreenPos) {
         widget.pixels(self.size).into_iter().for_each(|mut p| {
-            p.pos.x += offset.x;
-            p.pos.y += offset.y;
-            self.draw_pixel(p);
+            let x = p.pos.x.checked_add(offset.x);
+            let y = p.pos.y.checked_add(offset.y);
+            if let (Some(x), Some(y)) = (x, y) {
+                p.pos = ScreenPos::new(x, y);
+                self.draw_pixel(p);
+            }
         })
     }
 
-    fn in_view(&self, pos: ScreenPos) -> bool {
-        pos.x < self.size.width && pos.y < self.size.height
+    /// Draw a line from `from` to `to`, both ends included.
+    /// Anything outside of the viewport is clipped.
+    ///
+    /// ```
+    /// # use tinybit::*;
+    /// # use tinybit::theme::Style;
+    /// let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(10, 10));
+    /// let style = Style::new(Some(Color::Red), None);
+    /// viewport.draw_line(ScreenPos::new(0, 0), ScreenPos::new(9, 3), '*', style);
+    /// viewport.draw_rect(ScreenRect::new(ScreenPos::new(1, 1), ScreenSize::new(4, 3)), '#', style);
+    /// viewport.fill_ellipse(ScreenPos::new(5, 5), 4, 2, 'o', style);
+    /// ```
+    pub fn draw_line(&mut self, from: ScreenPos, to: ScreenPos, glyph: impl Into<Glyph>, style: Style) {
+        let glyph = glyph.into();
+        let from = (from.x as i32, from.y as i32);
+        let to = (to.x as i32, to.y as i32);
+        line(from, to, |x, y| self.plot(x, y, glyph, style));
     }
 
-    fn offset(&self, pos: ScreenPos) -> ScreenPos {
-        ScreenPos::new(pos.x + self.position.x, pos.y + self.position.y)
+    /// Draw the outline of a rect.
+    /// Anything outside of the viewport is clipped.
+    pub fn draw_rect(&mut self, rect: ScreenRect, glyph: impl Into<Glyph>, style: Style) {
+        if rect.is_empty() {
+            return;
+        }
+
+        let glyph = glyph.into();
+        let (min_x, min_y) = (rect.origin.x as i32, rect.origin.y as i32);
+        let max_x = min_x + rect.size.width as i32 - 1;
+        let max_y = min_y + rect.size.height as i32 - 1;
+
+        // Only the part of the sides that is in view
+        let (width, height) = (self.size.width as i32, self.size.height as i32);
+        for x in min_x..=max_x.min(width - 1) {
+            self.plot(x, min_y, glyph, style);
+            self.plot(x, max_y, glyph, style);
+        }
+
+        for y in min_y + 1..max_y.min(height) {
+            self.plot(min_x, y, glyph, style);
+            self.plot(max_x, y, glyph, style);
+        }
     }
 
-    pub(crate) fn pixels(&mut self) -> Vec<Pixel> {
-        let mut pixels = Vec::<Pixel>::new();
-
-        for (new, old) in self
-            .new_buf
-            .pixels
-            .iter()
-            .enumerate()
-            .zip(&self.old_buf.pixels)
-        {
-            match (new, old) {
-                ((index, Some(pixel)), _) => {
-                    let pos = self.offset(self.new_buf.index_to_coords(index));
-                    let mut pixel = *pixel;
-                    pixel.pos = pos;
-                    pixels.push(pixel);
-                }
-                ((index, None), Some(_)) => {
-                    let pos = self.offset(self.new_buf.index_to_coords(index));
-                    pixels.push(Pixel::white(' ', pos));
-                }
-                ((_, None), None) => {}
+    /// Fill a rect.
+    /// Anything outside of the viewport is clipped.
+    pub fn fill_rect(&mut self, rect: ScreenRect, glyph: impl Into<Glyph>, style: Style) {
+        let glyph = glyph.into();
+        let max_x = (rect.origin.x as u32 + rect.size.width as u32).min(self.size.width as u32) as u16;
+        let max_y = (rect.origin.y as u32 + rect.size.height as u32).min(self.size.height as u32) as u16;
+
+        for y in rect.origin.y..max_y {
+            for x in rect.origin.x..max_x {
+                self.draw_pixel(style.pixel(glyph, ScreenPos::new(x, y)));
             }
         }
+    }
 
-        swap(&mut self.new_buf, &mut self.old_buf);
-        self.new_buf.pixels.iter_mut().for_each(|opt| {
-            opt.take();
+    /// Draw the outline of a circle.
+    /// Anything outside of the viewport is clipped.
+    ///
+    /// Cells are usually twice as tall as they are wide, so a circle
+    /// looks like a tall ellipse. Use `draw_ellipse` with a horizontal radius
+    /// twice the vertical radius for something that looks round.
+    pub fn draw_circle(&mut self, center: ScreenPos, radius: u16, glyph: impl Into<Glyph>, style: Style) {
+        self.draw_ellipse(center, radius, radius, glyph, style);
+    }
+
+    /// Fill a circle.
+    /// Anything outside of the viewport is clipped.
+    pub fn fill_circle(&mut self, center: ScreenPos, radius: u16, glyph: impl Into<Glyph>, style: Style) {
+        self.fill_ellipse(center, radius, radius, glyph, style);
+    }
+
+    /// Draw the outline of an ellipse with a horizontal radius of `radius_x`
+    /// and a vertical radius of `radius_y`.
+    /// Anything outside of the viewport is clipped.
+    pub fn draw_ellipse(
+        &mut self,
+        center: ScreenPos,
+        radius_x: u16,
+        radius_y: u16,
+        glyph: impl Into<Glyph>,
+        style: Style,
+    ) {
+        let ellipse = Ellipse::new(center, radius_x, radius_y);
+        let glyph = glyph.into();
+
+        // A cell is on the outline if it's inside the ellipse,
+        // next to a cell that isn't
+        ellipse.for_each(self.size, |x, y| {
+            let edge = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                .iter()
+                .any(|(x, y)| !ellipse.contains(*x, *y));
+            if edge {
+                self.plot(x, y, glyph, style);
+            }
         });
+    }
+
+    /// Fill an ellipse with a horizontal radius of `radius_x`
+    /// and a vertical radius of `radius_y`.
+    /// Anything outside of the viewport is clipped.
+    pub fn fill_ellipse(
+        &mut self,
+        center: ScreenPos,
+        radius_x: u16,
+        radius_y: u16,
+        glyph: impl Into<Glyph>,
+        style: Style,
+    ) {
+        let glyph = glyph.into();
+        let size = self.size;
+        Ellipse::new(center, radius_x, radius_y).for_each(size, |x, y| self.plot(x, y, glyph, style));
+    }
 
+    /// Fill the area around `pos` on the current layer.
+    ///
+    /// The area is every cell connected to `pos` (horizontally or vertically)
+    /// that has the same pixel as `pos`, or no pixel if there is none at `pos`.
+    /// The area stops at the edge of the viewport.
+    pub fn flood_fill(&mut self, pos: ScreenPos, glyph: impl Into<Glyph>, style: Style) {
+        if !self.in_view(pos) {
+            return;
+        }
+
+        let buf = &self.layers[self.active_layer].buf;
+        let key = |pixel: Option<Pixel>| pixel.map(|p| (p.glyph, p.fg_color, p.bg_color, p.attributes));
+        let target = key(buf.get_pixel(pos));
+
+        let width = self.size.width as usize;
+        let mut area = Vec::new();
+        let mut visited = vec![false; width * self.size.height as usize];
+        let mut stack = vec![pos];
+
+        while let Some(pos) = stack.pop() {
+            let index = pos.y as usize * width + pos.x as usize;
+            if visited[index] || key(buf.get_pixel(pos)) != target {
+                continue;
+            }
+            visited[index] = true;
+            area.push(pos);
+
+            if pos.x > 0 {
+                stack.push(ScreenPos::new(pos.x - 1, pos.y));
+            }
+            if pos.y > 0 {
+                stack.push(ScreenPos::new(pos.x, pos.y - 1));
+            }
+            if pos.x + 1 < self.size.width {
+                stack.push(ScreenPos::new(pos.x + 1, pos.y));
+            }
+            if pos.y + 1 < self.size.height {
+                stack.push(ScreenPos::new(pos.x, pos.y + 1));
+            }
+        }
+
+        let glyph = glyph.into();
+        for pos in area {
+            self.draw_pixel(style.pixel(glyph, pos));
+        }
+    }
+
+    // Draw a pixel at a position that might be off screen
+    fn plot(&mut self, x: i32, y: i32, glyph: Glyph, style: Style) {
+        if x >= 0 && y >= 0 && x <= u16::MAX as i32 && y <= u16::MAX as i32 {
+            self.draw_pixel(style.pixel(glyph, ScreenPos::new(x as u16, y as u16)));
+        }
+    }
+
+    /// Convert a position on screen to a position relative to the viewport.
+    /// Returns `None` if the position is outside of the viewport.
+    pub fn to_local(&self, pos: ScreenPos) -> Option<ScreenPos> {
+        let x = pos.x.checked_sub(self.position.x)?;
+        let y = pos.y.checked_sub(self.position.y)?;
+        let local = ScreenPos::new(x, y);
+        match self.in_view(local) {
+            true => Some(local),
+            false => None,
+        }
+    }
+
+    fn in_view(&self, pos: ScreenPos) -> bool {
+        pos.x < self.size.width && pos.y < self.size.height
+    }
+
+    // Composite all layers into `new_buf`, from the bottom to the top,
+    // and clear the layers that are not retained.
+    pub(crate) fn compose(&mut self) -> &PixelBuffer {
+        let Self { layers, new_buf, .. } = self;
+
+        new_buf.clear();
+
+        for layer in layers.iter_mut() {
+            for pixel in layer.buf.pixels.iter().flatten() {
+                new_buf.blend_pixel(*pixel);
+            }
+
+            if !layer.retain {
+                layer.buf.clear();
+            }
+        }
+
+        new_buf
+    }
+
+    pub(crate) fn pixels(&mut self) -> Vec<Pixel> {
+        self.compose();
+        let pixels = match std::mem::take(&mut self.invalidated) {
+            true => self.new_buf.frame(self.position),
+            false => self.new_buf.diff(&self.old_buf, self.position),
+        };
+        swap(&mut self.new_buf, &mut self.old_buf);
         pixels
     }
 }
 
+// Every point on the line from `from` to `to`, both ends included,
+// using Bresenham's line algorithm
+pub(crate) fn line(from: (i32, i32), to: (i32, i32), mut f: impl FnMut(i32, i32)) {
+    let (mut x, mut y) = from;
+    let (to_x, to_y) = to;
+
+    let dx = (to_x - x).abs();
+    let dy = -(to_y - y).abs();
+    let step_x = if x < to_x { 1 } else { -1 };
+    let step_y = if y < to_y { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    loop {
+        f(x, y);
+        if x == to_x && y == to_y {
+            break;
+        }
+
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if e2 <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+// The cells inside an ellipse.
+// The radii are measured to the edge of the outermost cells,
+// which gives rounder shapes for small radii.
+struct Ellipse {
+    center_x: i32,
+    center_y: i32,
+    radius_x: i32,
+    radius_y: i32,
+}
+
+impl Ellipse {
+    fn new(center: ScreenPos, radius_x: u16, radius_y: u16) -> Self {
+        Self {
+            center_x: center.x as i32,
+            center_y: center.y as i32,
+            radius_x: radius_x as i32,
+            radius_y: radius_y as i32,
+        }
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        // (x / (rx + 0.5))^2 + (y / (ry + 0.5))^2 <= 1, without the fractions.
+        // This overflows an i64 for large radii.
+        let rx = (2 * self.radius_x + 1) as i128;
+        let ry = (2 * self.radius_y + 1) as i128;
+        let x = 2 * (x - self.center_x) as i128;
+        let y = 2 * (y - self.center_y) as i128;
+        x * x * ry * ry + y * y * rx * rx <= rx * rx * ry * ry
+    }
+
+    // Every cell inside the ellipse that is also inside `0, 0` to `size`
+    fn for_each(&self, size: ScreenSize, mut f: impl FnMut(i32, i32)) {
+        let min_x = (self.center_x - self.radius_x).max(0);
+        let max_x = (self.center_x + self.radius_x).min(size.width as i32 - 1);
+        let min_y = (self.center_y - self.radius_y).max(0);
+        let max_y = (self.center_y + self.radius_y).min(size.height as i32 - 1);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if self.contains(x, y) {
+                    f(x, y);
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -141,7 +496,7 @@ mod test {
         let pixels = positions
             .into_iter()
             .zip(glyphs)
-            .map(|(p, g)| Pixel::new(g, cam.to_screen(p), None, None))
+            .map(|(p, g)| Pixel::new(g, cam.to_screen(p).unwrap(), None, None))
             .collect::<Vec<_>>();
 
         view.draw_pixels(pixels);
@@ -155,4 +510,218 @@ mod test {
 
         assert_eq!(&drawn_pixels, &[a, b, c, d]);
     }
+
+    #[test]
+    fn only_changed_pixels() {
+        let mut view = viewport();
+
+        let a = Pixel::white('A', ScreenPos::new(0, 0));
+        let b = Pixel::white('B', ScreenPos::new(1, 0));
+        view.draw_pixels(vec![a, b]);
+        assert_eq!(view.pixels().len(), 2);
+
+        // Same pixels, nothing to draw
+        view.draw_pixels(vec![a, b]);
+        assert!(view.pixels().is_empty());
+
+        // Attributes are part of the pixel
+        let bold_b = b.with_attributes(Attributes::BOLD);
+        view.draw_pixels(vec![a, bold_b]);
+        let mut expected = bold_b;
+        expected.pos = ScreenPos::new(3, 2);
+        assert_eq!(view.pixels(), vec![expected]);
+    }
+
+    #[test]
+    fn layers_composite() {
+        let mut view = viewport();
+
+        // Drawn on top, even though it's drawn first
+        view.set_layer(2);
+        view.draw_pixel(Pixel::new('@', ScreenPos::new(0, 0), Some(Color::White), None));
+
+        view.set_layer(0);
+        view.draw_pixel(Pixel::new('.', ScreenPos::new(0, 0), None, Some(Color::Green)));
+        view.draw_pixel(Pixel::new('.', ScreenPos::new(1, 0), None, Some(Color::Green)));
+
+        let expected = vec![
+            Pixel::new('@', ScreenPos::new(2, 2), Some(Color::White), Some(Color::Green)),
+            Pixel::new('.', ScreenPos::new(3, 2), None, Some(Color::Green)),
+        ];
+        assert_eq!(view.pixels(), expected);
+    }
+
+    #[test]
+    fn retained_layer() {
+        let mut view = viewport();
+        view.retain_layer(1, true);
+        view.set_layer(1);
+        view.draw_pixel(Pixel::white('#', ScreenPos::new(0, 0)));
+        assert_eq!(view.pixels().len(), 1);
+
+        // Still there, so nothing changed
+        assert!(view.pixels().is_empty());
+
+        view.clear_layer(1);
+        assert_eq!(view.pixels(), vec![Pixel::white(' ', ScreenPos::new(2, 2))]);
+    }
+
+    #[test]
+    fn invalidate() {
+        let mut view = viewport();
+        view.draw_pixel(Pixel::white('A', ScreenPos::new(0, 0)));
+        assert_eq!(view.pixels().len(), 1);
+
+        view.invalidate();
+        view.draw_pixel(Pixel::white('A', ScreenPos::new(0, 0)));
+        let pixels = view.pixels();
+        assert_eq!(pixels.len(), 36);
+        assert_eq!(pixels[0], Pixel::white('A', ScreenPos::new(2, 2)));
+        assert_eq!(pixels[1], Pixel::white(' ', ScreenPos::new(3, 2)));
+
+        // Back to only the changes
+        view.draw_pixel(Pixel::white('A', ScreenPos::new(0, 0)));
+        assert!(view.pixels().is_empty());
+    }
+
+    #[test]
+    fn resize_keeps_content() {
+        let mut view = viewport();
+        view.retain_layer(0, true);
+        view.draw_pixel(Pixel::white('A', ScreenPos::new(0, 0)));
+        view.draw_pixel(Pixel::white('B', ScreenPos::new(5, 0)));
+        view.draw_pixel(Pixel::white('中', ScreenPos::new(2, 1)));
+        view.pixels();
+
+        view.resize(3, 2);
+        let glyphs = view.pixels().into_iter().map(|p| p.glyph).collect::<Vec<_>>();
+        // The wide glyph no longer fits, `B` is outside of the viewport
+        assert_eq!(glyphs, vec!['A', ' ', ' ', ' ', ' ', ' ']);
+    }
+
+    #[test]
+    fn diff_after_resize() {
+        let mut view = viewport();
+        view.retain_layer(0, true);
+        view.draw_pixel(Pixel::white('A', ScreenPos::new(0, 0)));
+        view.set_layer(1);
+        view.draw_pixel(Pixel::white('x', ScreenPos::new(1, 0)));
+        view.pixels();
+
+        // The first render after a resize is the whole frame:
+        // the retained layer, and what was drawn since the resize
+        view.resize(4, 2);
+        view.draw_pixel(Pixel::white('y', ScreenPos::new(1, 1)));
+        let glyphs = view.pixels().into_iter().map(|p| p.glyph).collect::<Vec<_>>();
+        assert_eq!(glyphs, vec!['A', ' ', ' ', ' ', ' ', 'y', ' ', ' ']);
+
+        // After that only the changes, with a blank where `y` was
+        let pixels = view.pixels();
+        assert_eq!(pixels, vec![Pixel::white(' ', ScreenPos::new(3, 3))]);
+        assert!(view.pixels().is_empty());
+    }
+
+    #[test]
+    fn clip_widget() {
+        let mut view = viewport();
+        let text = widgets::Text::new("abc", None, None);
+        view.draw_widget(&text, ScreenPos::new(4, 0));
+        view.draw_widget(&text, ScreenPos::new(u16::MAX, u16::MAX));
+
+        let glyphs = view.pixels().into_iter().map(|p| p.glyph).collect::<Vec<_>>();
+        assert_eq!(glyphs, vec!['a', 'b']);
+    }
+
+    // The glyphs on the current layer, one string per row
+    fn rows(view: &Viewport) -> Vec<String> {
+        let buf = &view.layers[view.active_layer].buf;
+        (0..view.size.height)
+            .map(|y| {
+                (0..view.size.width)
+                    .map(|x| buf.get_pixel(ScreenPos::new(x, y)).map(|p| p.glyph.as_str().to_string()))
+                    .map(|glyph| glyph.unwrap_or_else(|| ".".to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draw_lines() {
+        let mut view = viewport();
+        let style = Style::default();
+        view.draw_line(ScreenPos::new(0, 0), ScreenPos::new(5, 2), '*', style);
+        view.draw_line(ScreenPos::new(1, 5), ScreenPos::new(1, 3), '|', style);
+        // Clipped
+        view.draw_line(ScreenPos::new(3, 5), ScreenPos::new(20, 5), '-', style);
+
+        assert_eq!(
+            rows(&view),
+            vec!["**....", "..**..", "....**", ".|....", ".|....", ".|.---"]
+        );
+    }
+
+    #[test]
+    fn draw_rects() {
+        let mut view = viewport();
+        let style = Style::new(Some(Color::Red), None);
+        view.draw_rect(ScreenRect::new(ScreenPos::new(0, 0), ScreenSize::new(4, 3)), '#', style);
+        view.fill_rect(ScreenRect::new(ScreenPos::new(4, 4), ScreenSize::new(10, 10)), '%', style);
+        // Nothing is drawn for an empty rect
+        view.draw_rect(ScreenRect::new(ScreenPos::new(5, 0), ScreenSize::new(0, 3)), '#', style);
+
+        assert_eq!(
+            rows(&view),
+            vec!["####..", "#..#..", "####..", "......", "....%%", "....%%"]
+        );
+        let pixel = view.layers[0].buf.get_pixel(ScreenPos::new(5, 5)).unwrap();
+        assert_eq!(pixel.fg_color, Some(Color::Red));
+    }
+
+    #[test]
+    fn draw_circles() {
+        let mut view = viewport();
+        let style = Style::default();
+        view.draw_circle(ScreenPos::new(2, 2), 2, 'o', style);
+        // Clipped at the top left corner
+        view.fill_ellipse(ScreenPos::new(0, 5), 2, 1, '@', style);
+
+        assert_eq!(
+            rows(&view),
+            vec![".ooo..", "o...o.", "o...o.", "o...o.", "@@oo..", "@@@..."]
+        );
+    }
+
+    #[test]
+    fn flood_fill() {
+        let mut view = viewport();
+        let style = Style::default();
+        view.draw_rect(ScreenRect::new(ScreenPos::new(0, 0), ScreenSize::new(4, 4)), '#', style);
+        view.flood_fill(ScreenPos::new(1, 1), '~', style);
+        view.flood_fill(ScreenPos::new(5, 5), ' ', style);
+        // Replace the border, which isn't connected to the corner at the bottom right
+        view.flood_fill(ScreenPos::new(0, 0), '=', Style::new(Some(Color::Blue), None));
+
+        assert_eq!(
+            rows(&view),
+            vec!["====  ", "=~~=  ", "=~~=  ", "====  ", "      ", "      "]
+        );
+    }
+
+    #[test]
+    fn draw_large_shapes() {
+        let mut view = viewport();
+        let style = Style::default();
+        let huge = ScreenSize::new(u16::MAX, u16::MAX);
+        view.fill_rect(ScreenRect::new(ScreenPos::new(5, 5), huge), '%', style);
+        view.draw_rect(ScreenRect::new(ScreenPos::new(4, 4), huge), '#', style);
+        // The viewport is outside of the circle, in the corner of its bounding box
+        view.draw_circle(ScreenPos::new(40000, 40000), 40000, 'o', style);
+        assert_eq!(
+            rows(&view),
+            vec!["......", "......", "......", "......", "....##", "....#%"]
+        );
+
+        view.fill_circle(ScreenPos::new(u16::MAX, 0), u16::MAX, '@', style);
+        assert!(rows(&view).iter().all(|row| row == "@@@@@@"));
+    }
 }
@@ -4,8 +4,23 @@
 //! use tinybit::widgets::Text;
 //! let text = Text::new("Hello, World", None, None);
 //! ```
-use crate::{Color, Pixel, ScreenPos, ScreenSize};
-use crate::events::{KeyCode, KeyEvent};
+//!
+//! Widgets can also be created with a `Style`, or with the styles of a `Theme`:
+//!
+//! ```
+//! # use tinybit::theme::Theme;
+//! # use tinybit::widgets::{Border, TextField};
+//! let theme = Theme::from_json(r#"{ "styles": { "ui": { "fg": "grey" } } }"#).unwrap();
+//! let border = Border::themed("╭─╮│╯─╰│".to_string(), &theme);
+//! let input = TextField::themed(&theme);
+//! ```
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::glyph::columns;
+use crate::viewport::line;
+use crate::theme::{Style, Theme};
+use crate::{Attributes, Color, Glyph, Pixel, ScreenPos, ScreenSize};
+use crate::events::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 
 pub trait Widget {
     fn pixels(&self, size: ScreenSize) -> Vec<Pixel>;
@@ -15,6 +30,9 @@ pub trait Widget {
 //     - Text -
 // -----------------------------------------------------------------------------
 /// Render a text string as a specified location.
+/// Wide glyphs take up two columns.
+///
+/// Use `Text::styled` or `Text::themed` for text with attributes.
 pub struct Text(pub String, pub Option<Color>, pub Option<Color>);
 
 impl Text {
@@ -22,6 +40,19 @@ impl Text {
     pub fn new(s: impl Into<String>, fg: Option<Color>, bg: Option<Color>) -> Self {
         Self(s.into(), fg, bg)
     }
+
+    /// Make a new text widget with a style, including its attributes.
+    pub fn styled(s: impl Into<String>, style: Style) -> StyledText {
+        StyledText {
+            text: Self::new(s, style.fg_color, style.bg_color),
+            attributes: style.attributes,
+        }
+    }
+
+    /// Make a new text widget with the `ui.text` style of the theme.
+    pub fn themed(s: impl Into<String>, theme: &Theme) -> StyledText {
+        Self::styled(s, theme.style("ui.text"))
+    }
 }
 
 impl From<String> for Text {
@@ -35,8 +66,37 @@ impl Widget for Text {
         self.0
             .split('\n')
             .enumerate()
-            .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, c)| (y as u16, x as u16, c)))
-            .map(|(y, x, c)| Pixel::new(c, ScreenPos::new(x, y), self.1, self.2))
+            .flat_map(|(y, line)| columns(line).map(move |(x, glyph)| (y as u16, x, glyph)))
+            .map(|(y, x, glyph)| Pixel::new(glyph, ScreenPos::new(x, y), self.1, self.2))
+            .collect()
+    }
+}
+
+/// A text widget drawn with text attributes.
+/// Created with `Text::styled` or `Text::themed`.
+pub struct StyledText {
+    text: Text,
+    attributes: Attributes,
+}
+
+impl StyledText {
+    /// The text and its colours
+    pub fn text(&self) -> &Text {
+        &self.text
+    }
+
+    /// The text attributes
+    pub fn attributes(&self) -> Attributes {
+        self.attributes
+    }
+}
+
+impl Widget for StyledText {
+    fn pixels(&self, size: ScreenSize) -> Vec<Pixel> {
+        self.text
+            .pixels(size)
+            .into_iter()
+            .map(|pixel| pixel.with_attributes(self.attributes))
             .collect()
     }
 }
@@ -48,8 +108,7 @@ impl Widget for Text {
 /// See the `new` function for more details.
 pub struct Border {
     s: String,
-    fg_color: Option<Color>,
-    bg_color: Option<Color>,
+    style: Style,
 }
 
 impl Border {
@@ -65,8 +124,20 @@ impl Border {
     /// GFFFFFFE
     /// ```
     pub fn new(s: String, fg_color: Option<Color>, bg_color: Option<Color>) -> Self {
+        Self::styled(s, Style::new(fg_color, bg_color))
+    }
+
+    /// Create a new border with a style.
+    /// See `new` for the chars in `s`.
+    pub fn styled(s: String, style: Style) -> Self {
         debug_assert!(s.chars().count() >= 8);
-        Self { s, fg_color, bg_color }
+        Self { s, style }
+    }
+
+    /// Create a new border with the `ui.border` style of the theme.
+    /// See `new` for the chars in `s`.
+    pub fn themed(s: String, theme: &Theme) -> Self {
+        Self::styled(s, theme.style("ui.border"))
     }
 }
 
@@ -84,32 +155,28 @@ impl Widget for Border {
         let top_left = chars[0];
 
         let mut sides = (1..size.height - 1) // Left
-            .into_iter()
-            .map(|y| Pixel::new(left, ScreenPos::new(0, y), self.fg_color, self.bg_color))
+            .map(|y| self.style.pixel(left, ScreenPos::new(0, y)))
             .collect::<Vec<_>>();
 
         sides.append(&mut (1..size.height - 1) // Right
-            .into_iter()
-            .map(|y| Pixel::new(right, ScreenPos::new(0 + size.width - 1, y), self.fg_color, self.bg_color))
+            .map(|y| self.style.pixel(right, ScreenPos::new(size.width - 1, y)))
             .collect::<Vec<_>>());
 
         let mut top = (1..size.width - 1)
-            .into_iter()
-            .map(|x| Pixel::new(top, ScreenPos::new(x, 0), self.fg_color, self.bg_color))
+            .map(|x| self.style.pixel(top, ScreenPos::new(x, 0)))
             .collect::<Vec<_>>();
 
         top.append(&mut (1..size.width - 1) // Bottom
-            .into_iter()
-            .map(|x| Pixel::new(bot, ScreenPos::new(x, size.height - 1), self.fg_color, self.bg_color))
+            .map(|x| self.style.pixel(bot, ScreenPos::new(x, size.height - 1)))
             .collect::<Vec<_>>());
 
         top.append(&mut sides);
 
         // Corners
-        top.push(Pixel::new(top_left, ScreenPos::zero(), self.fg_color, self.bg_color));
-        top.push(Pixel::new(top_right, ScreenPos::new(size.width - 1, 0), self.fg_color, self.bg_color));
-        top.push(Pixel::new(bot_right, ScreenPos::new(size.width - 1, size.height - 1), self.fg_color, self.bg_color));
-        top.push(Pixel::new(bot_left, ScreenPos::new(0, size.height - 1), self.fg_color, self.bg_color));
+        top.push(self.style.pixel(top_left, ScreenPos::zero()));
+        top.push(self.style.pixel(top_right, ScreenPos::new(size.width - 1, 0)));
+        top.push(self.style.pixel(bot_right, ScreenPos::new(size.width - 1, size.height - 1)));
+        top.push(self.style.pixel(bot_left, ScreenPos::new(0, size.height - 1)));
 
         top
     }
@@ -119,6 +186,9 @@ impl Widget for Border {
 //     - Text widget -
 // -----------------------------------------------------------------------------
 /// A text input field.
+///
+/// The cursor moves one grapheme cluster at a time,
+/// so combining marks and wide glyphs are edited as a whole.
 pub struct TextField {
     pub text: String,
     pub password: bool,
@@ -126,14 +196,21 @@ pub struct TextField {
     pub submit: bool,
     pub enabled: bool,
     pub max_length: Option<usize>,
-    fg_color: Option<Color>,
-    bg_color: Option<Color>,
+    style: Style,
+    cursor_style: Option<Style>,
     cursor: usize,
 }
 
 impl TextField {
     /// Construct a new instance of an input.
     pub fn new(fg_color: Option<Color>, bg_color: Option<Color>) -> Self {
+        Self::styled(Style::new(fg_color, bg_color))
+    }
+
+    /// Construct a new input with a style.
+    /// The cursor is drawn in black, on the foreground colour of the style
+    /// (or on white if the style has no foreground colour).
+    pub fn styled(style: Style) -> Self {
         Self {
             text: String::new(),
             password: false,
@@ -141,12 +218,20 @@ impl TextField {
             submit: false,
             enabled: true,
             max_length: None,
-            fg_color,
-            bg_color,
+            style,
+            cursor_style: None,
             cursor: 0,
         }
     }
 
+    /// Construct a new input with the `ui.text` style of the theme,
+    /// and the cursor drawn with the `ui.focus` style.
+    pub fn themed(theme: &Theme) -> Self {
+        let mut field = Self::styled(theme.style("ui.text"));
+        field.cursor_style = Some(theme.style("ui.focus"));
+        field
+    }
+
     /// Clear the input and place the cursor
     /// at the start.
     pub fn clear(&mut self) {
@@ -158,7 +243,28 @@ impl TextField {
     /// This hides the cursor.
     pub fn unfocus(&mut self) {
         self.focus = false;
-        self.cursor = self.text.chars().count();
+        self.cursor = self.len();
+    }
+
+    // Number of grapheme clusters in the text
+    fn len(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+
+    // Byte offset of the grapheme cluster at `index`
+    fn byte_offset(&self, index: usize) -> usize {
+        self.text
+            .grapheme_indices(true)
+            .nth(index)
+            .map(|(offset, _)| offset)
+            .unwrap_or_else(|| self.text.len())
+    }
+
+    // Remove the grapheme cluster at `index`
+    fn remove(&mut self, index: usize) {
+        let start = self.byte_offset(index);
+        let end = self.byte_offset(index + 1);
+        self.text.replace_range(start..end, "");
     }
 
     /// Pass a `KeyEvent` to the input to build
@@ -176,72 +282,463 @@ impl TextField {
             KeyCode::Left if self.cursor > 0 => {
                 self.cursor -= 1;
             }
-            KeyCode::Right if self.cursor < self.text.len() => {
+            KeyCode::Right if self.cursor < self.len() => {
                 self.cursor += 1;
             }
             KeyCode::Backspace if self.cursor > 0 => {
                 self.cursor -= 1;
-                self.text.remove(self.cursor);
+                self.remove(self.cursor);
             }
-            KeyCode::Delete if self.text.len() > 0 => {
-                if self.text.len() <= self.cursor {
+            KeyCode::Delete if !self.text.is_empty() => {
+                if self.len() <= self.cursor {
                     return;
                 }
-                self.text.remove(self.cursor);
-                if self.cursor > self.text.len() {
-                    self.cursor = self.text.len();
-                }
+                self.remove(self.cursor);
             }
             KeyCode::Enter => {
                 self.submit = true;
             }
             KeyCode::Char(c) => {
                 match self.max_length {
-                    Some(max_len) if max_len <= self.text.chars().count() => return,
+                    Some(max_len) if max_len <= self.len() => return,
                     _ => {}
                 }
 
-                self.text.insert(self.cursor, c);
-                self.cursor += 1;
+                // A combining mark joins the grapheme cluster before it,
+                // so the cursor is placed after whatever cluster `c` ended up in.
+                let offset = self.byte_offset(self.cursor);
+                self.text.insert(offset, c);
+                self.cursor = self.text[..offset + c.len_utf8()].graphemes(true).count();
             }
             _ => {}
         }
     }
+
+    /// Pass a `MouseEvent` to the input.
+    /// `offset` is the position of the input on screen,
+    /// i.e the viewport position plus the offset the input is drawn at.
+    ///
+    /// Clicking the input focuses it and moves the cursor,
+    /// clicking anywhere else removes focus.
+    pub fn mouse_event(&mut self, event: MouseEvent, offset: ScreenPos) {
+        if !self.enabled {
+            return;
+        }
+
+        if let MouseEventKind::Down(MouseButton::Left) = event.kind {
+            let column = match event.column.checked_sub(offset.x) {
+                Some(column) if event.row == offset.y => column,
+                _ => return self.unfocus(),
+            };
+
+            // The cursor can be placed one cell past the end of the text
+            let mut width = 0;
+            for (index, glyph) in self.text.graphemes(true).map(Glyph::new).enumerate() {
+                width += glyph.width();
+                if column < width {
+                    self.focus = true;
+                    self.cursor = index;
+                    return;
+                }
+            }
+
+            match column == width {
+                true => {
+                    self.focus = true;
+                    self.cursor = self.len();
+                }
+                false => self.unfocus(),
+            }
+        }
+    }
 }
 
 impl Widget for TextField {
     fn pixels(&self, _size: ScreenSize) -> Vec<Pixel> {
-        let mut pixels = self
-            .text
-            .chars()
-            .enumerate()
-            .map(|(x, c)| if self.password { (x, '*') } else { (x, c) })
-            .map(|(x, c)| Pixel::new(c, ScreenPos::new(x as u16, 0), self.fg_color, self.bg_color))
-            .collect::<Vec<Pixel>>();
+        let mut column = 0;
+        let mut cursor = None;
+        let mut pixels = Vec::new();
+
+        for (index, grapheme) in self.text.graphemes(true).enumerate() {
+            let glyph = match self.password {
+                true => Glyph::from('*'),
+                false => Glyph::new(grapheme),
+            };
+
+            if index == self.cursor {
+                cursor = Some((column, glyph));
+            }
+
+            pixels.push(self.style.pixel(glyph, ScreenPos::new(column, 0)));
+            column += glyph.width();
+        }
 
         if !self.focus || !self.enabled {
             return pixels;
         }
 
-        // Get char under cursor
-        let c = match self.password {
-            true => self
-                .text
-                .chars()
-                .nth(self.cursor)
-                .map(|_| '*')
-                .unwrap_or(' '),
-            false => self.text.chars().nth(self.cursor).unwrap_or(' '),
-        };
+        // Get the glyph under the cursor
+        let (column, glyph) = cursor.unwrap_or((column, Glyph::from(' ')));
 
         // Draw cursor
-        pixels.push(Pixel::new(
-            c,
-            ScreenPos::new(self.cursor as u16, 0),
-            Some(Color::Black),
-            Some(self.fg_color.unwrap_or(Color::White)),
-        ));
+        let cursor_style = self.cursor_style.unwrap_or_else(|| {
+            Style::new(Some(Color::Black), Some(self.style.fg_color.unwrap_or(Color::White)))
+        });
+        pixels.push(cursor_style.pixel(glyph, ScreenPos::new(column, 0)));
 
         pixels
     }
 }
+
+// -----------------------------------------------------------------------------
+//     - Canvas -
+// -----------------------------------------------------------------------------
+/// How a `Canvas` draws its dots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanvasMode {
+    /// Braille characters, with 2x4 dots per cell.
+    /// All the dots in a cell have the same colour.
+    Braille,
+    /// Half blocks (`▀` and `▄`), with 1x2 dots per cell.
+    /// Each dot has its own colour.
+    HalfBlock,
+}
+
+impl CanvasMode {
+    // The number of dots in a cell, horizontally and vertically
+    fn dots_per_cell(self) -> (u16, u16) {
+        match self {
+            CanvasMode::Braille => (2, 4),
+            CanvasMode::HalfBlock => (1, 2),
+        }
+    }
+}
+
+/// Draw with dots that are smaller than a cell.
+///
+/// The size of the canvas is given in cells, and dots are addressed
+/// from the top left corner.
+/// Cells without any dots are not drawn, so whatever is below them shows through.
+///
+/// ```
+/// # use tinybit::*;
+/// # use tinybit::widgets::{Canvas, CanvasMode};
+/// let mut canvas = Canvas::new(CanvasMode::Braille, ScreenSize::new(10, 5));
+/// assert_eq!((canvas.width(), canvas.height()), (20, 20));
+///
+/// canvas.line(ScreenPos::new(0, 19), ScreenPos::new(19, 0), Color::Green);
+/// canvas.set(ScreenPos::new(3, 3), Color::Red);
+///
+/// let mut viewport = Viewport::new(ScreenPos::zero(), ScreenSize::new(10, 5));
+/// viewport.draw_widget(&canvas, ScreenPos::zero());
+/// ```
+pub struct Canvas {
+    mode: CanvasMode,
+    size: ScreenSize,
+    /// The background colour of the cells with dots.
+    /// In half-block mode this is also the colour of a half without a dot.
+    /// Cells without any dots are not drawn.
+    pub background: Option<Color>,
+    dots: Vec<Option<Color>>,
+    // The colour of the last dot set in each cell, used by braille
+    cell_colors: Vec<Option<Color>>,
+}
+
+impl Canvas {
+    /// Create an empty canvas, `size` cells big.
+    ///
+    /// The size is clamped so every dot has a position,
+    /// e.g a braille canvas is at most `u16::MAX / 2` cells wide.
+    pub fn new(mode: CanvasMode, size: ScreenSize) -> Self {
+        let (dots_x, dots_y) = mode.dots_per_cell();
+        let size = ScreenSize::new(size.width.min(u16::MAX / dots_x), size.height.min(u16::MAX / dots_y));
+        let cells = size.width as usize * size.height as usize;
+        Self {
+            mode,
+            size,
+            background: None,
+            dots: vec![None; cells * dots_x as usize * dots_y as usize],
+            cell_colors: vec![None; cells],
+        }
+    }
+
+    /// The width in dots
+    pub fn width(&self) -> u16 {
+        self.size.width * self.mode.dots_per_cell().0
+    }
+
+    /// The height in dots
+    pub fn height(&self) -> u16 {
+        self.size.height * self.mode.dots_per_cell().1
+    }
+
+    /// Set a dot.
+    /// Dots outside of the canvas are ignored.
+    pub fn set(&mut self, pos: ScreenPos, color: Color) {
+        if let Some(index) = self.index(pos) {
+            self.dots[index] = Some(color);
+            let cell = self.cell_index(pos);
+            self.cell_colors[cell] = Some(color);
+        }
+    }
+
+    /// Remove a dot
+    pub fn unset(&mut self, pos: ScreenPos) {
+        if let Some(index) = self.index(pos) {
+            self.dots[index] = None;
+            if self.cell_is_empty(pos) {
+                let cell = self.cell_index(pos);
+                self.cell_colors[cell] = None;
+            }
+        }
+    }
+
+    /// The colour of a dot, or `None` if the dot isn't set
+    pub fn get(&self, pos: ScreenPos) -> Option<Color> {
+        self.index(pos).and_then(|index| self.dots[index])
+    }
+
+    /// Draw a line of dots from `from` to `to`, both ends included.
+    /// Dots outside of the canvas are ignored.
+    pub fn line(&mut self, from: ScreenPos, to: ScreenPos, color: Color) {
+        let from = (from.x as i32, from.y as i32);
+        let to = (to.x as i32, to.y as i32);
+        // The line is between two points on the canvas, so it's never negative
+        line(from, to, |x, y| self.set(ScreenPos::new(x as u16, y as u16), color));
+    }
+
+    /// Remove all dots
+    pub fn clear(&mut self) {
+        self.dots.iter_mut().for_each(|dot| *dot = None);
+        self.cell_colors.iter_mut().for_each(|color| *color = None);
+    }
+
+    fn index(&self, pos: ScreenPos) -> Option<usize> {
+        match pos.x < self.width() && pos.y < self.height() {
+            true => Some(pos.y as usize * self.width() as usize + pos.x as usize),
+            false => None,
+        }
+    }
+
+    fn cell_index(&self, pos: ScreenPos) -> usize {
+        let (dots_x, dots_y) = self.mode.dots_per_cell();
+        (pos.y / dots_y) as usize * self.size.width as usize + (pos.x / dots_x) as usize
+    }
+
+    // True if no dots are set in the cell with the dot at `pos`
+    fn cell_is_empty(&self, pos: ScreenPos) -> bool {
+        let (dots_x, dots_y) = self.mode.dots_per_cell();
+        let (x, y) = (pos.x / dots_x * dots_x, pos.y / dots_y * dots_y);
+        (y..y + dots_y).all(|y| (x..x + dots_x).all(|x| self.get(ScreenPos::new(x, y)).is_none()))
+    }
+
+    fn cell(&self, cell: ScreenPos) -> Option<Pixel> {
+        let dot = |x: u16, y: u16| {
+            let (dots_x, dots_y) = self.mode.dots_per_cell();
+            self.get(ScreenPos::new(cell.x * dots_x + x, cell.y * dots_y + y))
+        };
+
+        match self.mode {
+            CanvasMode::Braille => {
+                // The bit for each dot, in the order of the braille patterns block
+                const BITS: [(u16, u16, u32); 8] = [
+                    (0, 0, 0x01),
+                    (0, 1, 0x02),
+                    (0, 2, 0x04),
+                    (1, 0, 0x08),
+                    (1, 1, 0x10),
+                    (1, 2, 0x20),
+                    (0, 3, 0x40),
+                    (1, 3, 0x80),
+                ];
+                let bits = BITS
+                    .iter()
+                    .filter(|(x, y, _)| dot(*x, *y).is_some())
+                    .fold(0, |bits, (_, _, bit)| bits | bit);
+
+                if bits == 0 {
+                    return None;
+                }
+
+                let glyph = std::char::from_u32(0x2800 + bits)?;
+                let color = self.cell_colors[cell.y as usize * self.size.width as usize + cell.x as usize];
+                Some(Pixel::new(glyph, cell, color, self.background))
+            }
+            CanvasMode::HalfBlock => match (dot(0, 0), dot(0, 1)) {
+                (None, None) => None,
+                (Some(upper), lower) => Some(Pixel::new('▀', cell, Some(upper), lower.or(self.background))),
+                (None, Some(lower)) => Some(Pixel::new('▄', cell, Some(lower), self.background)),
+            },
+        }
+    }
+}
+
+impl Widget for Canvas {
+    fn pixels(&self, _size: ScreenSize) -> Vec<Pixel> {
+        (0..self.size.height)
+            .flat_map(|y| (0..self.size.width).map(move |x| ScreenPos::new(x, y)))
+            .filter_map(|cell| self.cell(cell))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::from(code)
+    }
+
+    fn field(text: &str) -> TextField {
+        let mut field = TextField::new(None, None);
+        field.focus = true;
+        text.chars().for_each(|c| field.event(key(KeyCode::Char(c))));
+        field
+    }
+
+    #[test]
+    fn text_wide_glyphs() {
+        let glyphs = Text::new("a中b", None, None)
+            .pixels(ScreenSize::new(10, 1))
+            .into_iter()
+            .map(|p| (p.glyph.to_string(), p.pos.x))
+            .collect::<Vec<_>>();
+        let expected = vec![("a".to_string(), 0), ("中".to_string(), 1), ("b".to_string(), 3)];
+        assert_eq!(glyphs, expected);
+    }
+
+    #[test]
+    fn text_combining_marks() {
+        let pixels = Text::new("e\u{301}x", None, None).pixels(ScreenSize::new(10, 1));
+        assert_eq!(pixels.len(), 2);
+        assert_eq!(pixels[0].glyph.as_str(), "e\u{301}");
+        assert_eq!(pixels[1].pos.x, 1);
+    }
+
+    #[test]
+    fn text_field_edit_graphemes() {
+        let mut field = field("a中e\u{301}");
+        assert_eq!(field.cursor, 3);
+
+        field.event(key(KeyCode::Backspace));
+        assert_eq!(field.text, "a中");
+
+        field.event(key(KeyCode::Left));
+        field.event(key(KeyCode::Delete));
+        assert_eq!(field.text, "a");
+        assert_eq!(field.cursor, 1);
+    }
+
+    #[test]
+    fn text_field_cursor_column() {
+        let mut field = field("中中");
+        field.event(key(KeyCode::Left));
+
+        let cursor = field.pixels(ScreenSize::new(10, 1)).pop().unwrap();
+        assert_eq!(cursor.glyph, '中');
+        assert_eq!(cursor.pos.x, 2);
+    }
+
+    #[test]
+    fn text_field_mouse() {
+        let mut field = field("a中b");
+        field.unfocus();
+
+        let click = |column, row| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: KeyModifiers::empty(),
+        };
+
+        // Second half of the wide glyph
+        field.mouse_event(click(12, 5), ScreenPos::new(10, 5));
+        assert!(field.focus);
+        assert_eq!(field.cursor, 1);
+
+        // Past the end of the text
+        field.mouse_event(click(14, 5), ScreenPos::new(10, 5));
+        assert_eq!(field.cursor, 3);
+
+        field.mouse_event(click(15, 5), ScreenPos::new(10, 5));
+        assert!(!field.focus);
+    }
+
+    #[test]
+    fn themed_widgets() {
+        let mut theme = Theme::new();
+        let text = Style::new(Some(Color::Grey), None).with_attributes(Attributes::ITALIC);
+        let focus = Style::new(Some(Color::Black), Some(Color::Yellow));
+        theme.set_style("ui", text);
+        theme.set_style("ui.focus", focus);
+
+        let border = Border::themed("12345678".to_string(), &theme);
+        assert!(border.pixels(ScreenSize::new(3, 3)).iter().all(|p| p.fg_color == Some(Color::Grey)));
+
+        let mut field = TextField::themed(&theme);
+        field.focus = true;
+        field.event(key(KeyCode::Char('a')));
+        let pixels = field.pixels(ScreenSize::new(5, 1));
+        assert_eq!(pixels[0], text.pixel('a', ScreenPos::zero()));
+        assert_eq!(pixels[1], focus.pixel(' ', ScreenPos::new(1, 0)));
+
+        let pixels = Text::themed("b", &theme).pixels(ScreenSize::new(5, 1));
+        assert_eq!(pixels, vec![text.pixel('b', ScreenPos::zero())]);
+    }
+
+    #[test]
+    fn braille_canvas() {
+        let mut canvas = Canvas::new(CanvasMode::Braille, ScreenSize::new(3, 1));
+        canvas.set(ScreenPos::new(0, 0), Color::Red);
+        canvas.set(ScreenPos::new(1, 3), Color::Green);
+        canvas.line(ScreenPos::new(2, 0), ScreenPos::new(3, 3), Color::Blue);
+        // Outside of the canvas
+        canvas.set(ScreenPos::new(6, 0), Color::Blue);
+
+        let expected = vec![
+            // The colour of the last dot set in the cell
+            Pixel::new('⢁', ScreenPos::new(0, 0), Some(Color::Green), None),
+            Pixel::new('⢣', ScreenPos::new(1, 0), Some(Color::Blue), None),
+        ];
+        assert_eq!(canvas.pixels(ScreenSize::new(3, 1)), expected);
+
+        canvas.unset(ScreenPos::new(0, 0));
+        canvas.unset(ScreenPos::new(1, 3));
+        assert_eq!(canvas.pixels(ScreenSize::new(3, 1)).len(), 1);
+
+        // The cell was emptied, so the colour of the removed dots is gone
+        canvas.set(ScreenPos::new(0, 0), Color::Red);
+        canvas.unset(ScreenPos::new(0, 0));
+        canvas.set(ScreenPos::new(0, 1), Color::Reset);
+        let pixels = canvas.pixels(ScreenSize::new(3, 1));
+        assert_eq!(pixels[0], Pixel::new('⠂', ScreenPos::zero(), Some(Color::Reset), None));
+
+        // Clamped, so the size in dots fits in a u16
+        let canvas = Canvas::new(CanvasMode::Braille, ScreenSize::new(40000, 1));
+        assert_eq!((canvas.width(), canvas.height()), (65534, 4));
+    }
+
+    #[test]
+    fn half_block_canvas() {
+        let mut canvas = Canvas::new(CanvasMode::HalfBlock, ScreenSize::new(3, 1));
+        canvas.background = Some(Color::Black);
+        assert_eq!((canvas.width(), canvas.height()), (3, 2));
+
+        canvas.set(ScreenPos::new(0, 0), Color::Red);
+        canvas.set(ScreenPos::new(0, 1), Color::Blue);
+        canvas.set(ScreenPos::new(1, 0), Color::Red);
+        canvas.set(ScreenPos::new(2, 1), Color::Blue);
+
+        let expected = vec![
+            Pixel::new('▀', ScreenPos::new(0, 0), Some(Color::Red), Some(Color::Blue)),
+            Pixel::new('▀', ScreenPos::new(1, 0), Some(Color::Red), Some(Color::Black)),
+            Pixel::new('▄', ScreenPos::new(2, 0), Some(Color::Blue), Some(Color::Black)),
+        ];
+        assert_eq!(canvas.pixels(ScreenSize::new(3, 1)), expected);
+
+        canvas.clear();
+        assert!(canvas.pixels(ScreenSize::new(3, 1)).is_empty());
+    }
+}